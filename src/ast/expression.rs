@@ -31,6 +31,16 @@ pub enum Expression {
         else_block: Option<Block>,
     },
     
+    Try {
+        body: Block,
+        handler: Block,
+    },
+    
+    Catch {
+        value: String,
+        handler: Block,
+    },
+    
     PendingCall(String),
     
     AbstractPrimitive(ConformValue),
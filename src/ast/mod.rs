@@ -1,9 +1,11 @@
 pub mod value;
+pub mod types;
 pub mod expression;
 pub mod statement;
 pub mod program;
 
 pub use value::*;
+pub use types::*;
 pub use expression::*;
 pub use statement::*;
 pub use program::*;
\ No newline at end of file
@@ -1,8 +1,17 @@
 use serde::{Deserialize, Serialize};
 use super::statement::Statement;
+use super::types::ValueType;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+    pub name: String,
+    pub value_type: ValueType,
+}
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Program {
+    #[serde(default)]
+    pub inputs: Vec<Input>,
     pub statements: Vec<Statement>,
     pub return_var: String,
 }
\ No newline at end of file
@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
 use super::expression::Expression;
 
+pub const RESERVED_PREFIX: char = '$';
+
+pub fn reserved_name(variable: &str, role: &str) -> String {
+    format!("{}{}_{}", RESERVED_PREFIX, variable, role)
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Statement {
     pub variable: String,
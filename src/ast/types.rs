@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use super::value::{Value, PrimitiveValue, ConformValue};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValueType {
+    Any,
+    Boolean,
+    Integer,
+    Float,
+    String,
+    Null,
+    List(Box<ValueType>),
+    Tuple(Vec<ValueType>),
+    Error,
+}
+
+impl ValueType {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Primitive(PrimitiveValue::Boolean(_)) => ValueType::Boolean,
+            Value::Primitive(PrimitiveValue::Integer(_)) => ValueType::Integer,
+            Value::Primitive(PrimitiveValue::Float(_)) => ValueType::Float,
+            Value::Primitive(PrimitiveValue::String(_)) => ValueType::String,
+            Value::Primitive(PrimitiveValue::Null) => ValueType::Null,
+            Value::List(elements) => {
+                let mut element_types = elements.iter().map(ValueType::of);
+                let element_type = match element_types.next() {
+                    Some(first) if element_types.all(|t| t == first) => first,
+                    _ => ValueType::Any,
+                };
+                ValueType::List(Box::new(element_type))
+            }
+            Value::Tuple(elements) => ValueType::Tuple(elements.iter().map(ValueType::of).collect()),
+            Value::Error(_) | Value::Rejected(_) => ValueType::Error,
+        }
+    }
+
+    pub fn of_conform(value: &ConformValue) -> Self {
+        let mut types = value.possibilities.iter().map(ValueType::of);
+        match types.next() {
+            Some(first) if types.all(|t| t == first) => first,
+            _ => ValueType::Any,
+        }
+    }
+
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (ValueType::Any, _) => true,
+            (ValueType::List(element_type), Value::List(elements)) => {
+                elements.iter().all(|e| element_type.accepts(e))
+            }
+            (ValueType::Tuple(types), Value::Tuple(elements)) => {
+                types.len() == elements.len()
+                    && types.iter().zip(elements).all(|(t, e)| t.accepts(e))
+            }
+            (ValueType::List(_), _) | (ValueType::Tuple(_), _) => false,
+            (expected, value) => *expected == ValueType::of(value),
+        }
+    }
+
+    pub fn accepts_conform(&self, value: &ConformValue) -> bool {
+        value.possibilities.iter().all(|v| self.accepts(v))
+    }
+
+    pub fn json_schema(&self) -> serde_json::Value {
+        match self {
+            ValueType::Any => json!({}),
+            ValueType::Boolean => json!({ "type": "boolean" }),
+            ValueType::Integer => json!({ "type": "integer" }),
+            ValueType::Float => json!({ "type": "number" }),
+            ValueType::String => json!({ "type": "string" }),
+            ValueType::Null => json!({ "type": "null" }),
+            ValueType::List(element) => json!({
+                "type": "array",
+                "items": element.json_schema(),
+            }),
+            ValueType::Tuple(elements) => json!({
+                "type": "array",
+                "prefixItems": elements.iter().map(ValueType::json_schema).collect::<Vec<_>>(),
+                "minItems": elements.len(),
+                "maxItems": elements.len(),
+            }),
+            ValueType::Error => json!({
+                "type": "object",
+                "properties": { "error": { "type": "string" } },
+                "required": ["error"],
+            }),
+        }
+    }
+
+    pub fn value_from_json(&self, json: &serde_json::Value) -> Option<Value> {
+        match (self, json) {
+            (ValueType::List(element), serde_json::Value::Array(items)) => {
+                items.iter().map(|item| element.value_from_json(item)).collect::<Option<_>>().map(Value::List)
+            }
+            (ValueType::Tuple(types), serde_json::Value::Array(items)) if types.len() == items.len() => {
+                types.iter().zip(items).map(|(t, item)| t.value_from_json(item)).collect::<Option<_>>().map(Value::Tuple)
+            }
+            (ValueType::Any, serde_json::Value::Array(items)) => {
+                items.iter().map(|item| ValueType::Any.value_from_json(item)).collect::<Option<_>>().map(Value::List)
+            }
+            (_, serde_json::Value::Array(_)) | (_, serde_json::Value::Object(_)) => None,
+            (value_type, json) => {
+                let primitive = match json {
+                    serde_json::Value::Null => PrimitiveValue::Null,
+                    serde_json::Value::Bool(b) => PrimitiveValue::Boolean(*b),
+                    serde_json::Value::String(s) => PrimitiveValue::String(s.clone()),
+                    serde_json::Value::Number(n) => match (value_type, n.as_i64()) {
+                        (ValueType::Float, _) | (_, None) => PrimitiveValue::Float(n.as_f64()?.into()),
+                        (_, Some(i)) => PrimitiveValue::Integer(i),
+                    },
+                    _ => return None,
+                };
+                let value = Value::Primitive(primitive);
+                value_type.accepts(&value).then_some(value)
+            }
+        }
+    }
+
+    pub fn is_compatible_with(&self, other: &ValueType) -> bool {
+        match (self, other) {
+            (ValueType::Any, _) | (_, ValueType::Any) => true,
+            (ValueType::List(a), ValueType::List(b)) => a.is_compatible_with(b),
+            (ValueType::Tuple(a), ValueType::Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.is_compatible_with(y))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    pub fn join(&self, other: &ValueType) -> ValueType {
+        match (self, other) {
+            (ValueType::List(a), ValueType::List(b)) => ValueType::List(Box::new(a.join(b))),
+            (ValueType::Tuple(a), ValueType::Tuple(b)) if a.len() == b.len() => {
+                ValueType::Tuple(a.iter().zip(b).map(|(x, y)| x.join(y)).collect())
+            }
+            (a, b) if a == b => a.clone(),
+            _ => ValueType::Any,
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueType::Any => write!(f, "Any"),
+            ValueType::Boolean => write!(f, "Boolean"),
+            ValueType::Integer => write!(f, "Integer"),
+            ValueType::Float => write!(f, "Float"),
+            ValueType::String => write!(f, "String"),
+            ValueType::Null => write!(f, "Null"),
+            ValueType::Error => write!(f, "Error"),
+            ValueType::List(element) => write!(f, "List<{}>", element),
+            ValueType::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
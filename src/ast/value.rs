@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use super::types::ValueType;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum PrimitiveValue {
@@ -15,6 +16,14 @@ pub enum Value {
     Primitive(PrimitiveValue),
     List(Vec<Value>),
     Tuple(Vec<Value>),
+    Error(String),
+    Rejected(String),
+}
+
+impl Value {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Value::Error(_) | Value::Rejected(_))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -45,6 +54,35 @@ impl ConformValue {
         self.possibilities.len() == 1
     }
 
+    pub fn unknown(value_type: &ValueType) -> Option<Self> {
+        match value_type {
+            ValueType::Boolean => Some(ConformValue::uncertain(vec![
+                Value::Primitive(PrimitiveValue::Boolean(true)),
+                Value::Primitive(PrimitiveValue::Boolean(false)),
+            ])),
+            ValueType::Null => Some(ConformValue::certain(Value::Primitive(PrimitiveValue::Null))),
+            ValueType::Tuple(types) => {
+                let mut tuples = vec![Vec::new()];
+                for element_type in types {
+                    let elements = ConformValue::unknown(element_type)?;
+                    tuples = tuples.into_iter().flat_map(|tuple| {
+                        elements.possibilities.iter().map(move |element| {
+                            let mut tuple = tuple.clone();
+                            tuple.push(element.clone());
+                            tuple
+                        })
+                    }).collect();
+                }
+                Some(ConformValue::uncertain(tuples.into_iter().map(Value::Tuple)))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn failure(&self) -> Option<&Value> {
+        self.possibilities.iter().find(|v| v.is_failure())
+    }
+
     pub fn as_certain(&self) -> Option<&Value> {
         if self.is_certain() {
             self.possibilities.iter().next()
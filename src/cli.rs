@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use quasar::ast::*;
+use quasar::interpreter::{check_program, run, Breakpoint, Debugger, DependencyGraph, ParallelismReport, Stepper};
+use quasar::runtime::{
+    ExecutionOptions, ExecutionState, ExecutionStatus, FunctionRegistry, QuasarError, Result, RuntimeConfig, Trace,
+    inputs_from_json,
+};
+use quasar::syntax::{parse_program, parse_program_with_trivia, parse_value, print_program_with_trivia};
+use crate::dap::DapServer;
+use crate::lsp::LspServer;
+use crate::debug::{parse_breakpoint, run_debugger};
+use crate::repl::Repl;
+
+pub const EXIT_COMPLETED: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_STUCK: i32 = 3;
+pub const EXIT_CANCELLED: i32 = 4;
+
+const USAGE: &str = "\
+Usage: quasar <command> [options]
+
+Commands:
+  run <file>      execute a program
+  check <file>    parse and type-check a program
+  fmt <file>      print a program in canonical form
+  graph <file>    print the dependency graph of a program
+  plan <file>     estimate the number of calls, parallelism and latency of a program
+  repl            start an interactive session
+  debug <file>    execute a program under the debugger
+  dap             serve the Debug Adapter Protocol over stdin/stdout
+  lsp             serve the Language Server Protocol over stdin/stdout
+
+Options:
+  -y, --auto-approve     run external calls without asking for approval (run, repl, debug)
+  --policy <file>        load call policies and scheduling limits from a JSON file (run, repl, debug, dap, plan)
+  --fan-out <n>          dispatch a call once per possible argument when there are at most n (run, repl, debug)
+  --speculate            pre-dispatch pure calls in both branches of a pending if; needs -y (run, repl, debug)
+  --trace <file>         write a JSON Lines rewrite trace (run, debug)
+  --json                 print the result as JSON instead of the execution log (run, plan)
+  --input <name=value>   provide a declared input; value is a literal or a bare string (run, debug)
+  --inputs <file>        provide declared inputs from a JSON object (run, debug)
+  --break <breakpoint>   set a breakpoint: dispatch:<f>, bind:<x> or uncertain (debug)
+  --write                overwrite the file instead of printing it (fmt)
+  --check                exit with 1 if the file is not formatted (fmt)
+  --format <format>      output format: dot, mermaid or json (graph)
+  --length <name=n>      assume the list <name> has n elements (plan)
+
+Exit codes: 0 completed, 1 error, 2 usage, 3 stuck, 4 cancelled
+";
+
+#[derive(Debug, Default)]
+struct Args {
+    command: String,
+    file: Option<PathBuf>,
+    auto_approve: bool,
+    policy: Option<PathBuf>,
+    trace: Option<PathBuf>,
+    json: bool,
+    inputs: Vec<(String, String)>,
+    inputs_file: Option<PathBuf>,
+    breakpoints: Vec<Breakpoint>,
+    write: bool,
+    check: bool,
+    format: Option<String>,
+    lengths: Vec<(String, usize)>,
+    fan_out: Option<usize>,
+    speculate: bool,
+}
+
+pub async fn main(args: Vec<String>) -> i32 {
+    if args.first().is_some_and(|arg| matches!(arg.as_str(), "help" | "-h" | "--help")) {
+        print!("{}", USAGE);
+        return EXIT_COMPLETED;
+    }
+    
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    
+    let result = match args.command.as_str() {
+        "run" => run_file(&args).await,
+        "check" => check_file(&args),
+        "fmt" => format_file(&args),
+        "graph" => graph_file(&args),
+        "plan" => plan_file(&args),
+        "repl" => start_repl(&args).await,
+        "debug" => debug_file(&args).await,
+        "dap" => serve_dap(&args).await,
+        "lsp" => serve_lsp().await,
+        _ => unreachable!(),
+    };
+    
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        EXIT_ERROR
+    })
+}
+
+fn parse_args(args: Vec<String>) -> std::result::Result<Args, String> {
+    let mut iter = args.into_iter();
+    let mut parsed = Args {
+        command: iter.next().ok_or("Missing command")?,
+        ..Default::default()
+    };
+    if !matches!(parsed.command.as_str(), "run" | "check" | "fmt" | "graph" | "plan" | "repl" | "debug" | "dap" | "lsp") {
+        return Err(format!("Unknown command: {}", parsed.command));
+    }
+    
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| iter.next().ok_or(format!("Missing value for {}", flag));
+        match arg.as_str() {
+            "-y" | "--auto-approve" => parsed.auto_approve = true,
+            "--policy" => parsed.policy = Some(value(&arg)?.into()),
+            "--trace" => parsed.trace = Some(value(&arg)?.into()),
+            "--json" => parsed.json = true,
+            "--speculate" => parsed.speculate = true,
+            "--fan-out" => {
+                let limit = value(&arg)?;
+                parsed.fan_out = Some(limit.parse().map_err(|_| format!("Invalid fan-out limit: {}", limit))?);
+            }
+            "--input" => {
+                let input = value(&arg)?;
+                let (name, text) = input.split_once('=').ok_or(format!("Invalid input {}: expected name=value", input))?;
+                parsed.inputs.push((name.to_string(), text.to_string()));
+            }
+            "--inputs" => parsed.inputs_file = Some(value(&arg)?.into()),
+            "--break" => {
+                let spec = value(&arg)?;
+                parsed.breakpoints.push(parse_breakpoint(&spec.replacen(':', " ", 1)).map_err(|e| e.to_string())?);
+            }
+            "--write" => parsed.write = true,
+            "--check" => parsed.check = true,
+            "--format" => {
+                let format = value(&arg)?;
+                if !matches!(format.as_str(), "dot" | "mermaid" | "json") {
+                    return Err(format!("Unknown format: {}", format));
+                }
+                parsed.format = Some(format);
+            }
+            "--length" => {
+                let length = value(&arg)?;
+                let parsed_length = length.split_once('=')
+                    .and_then(|(name, n)| Some((name.to_string(), n.parse().ok()?)))
+                    .ok_or(format!("Invalid length {}: expected name=n", length))?;
+                parsed.lengths.push(parsed_length);
+            }
+            _ if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            _ if parsed.file.is_none() => parsed.file = Some(arg.into()),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    
+    if !matches!(parsed.command.as_str(), "repl" | "dap" | "lsp") && parsed.file.is_none() {
+        return Err(format!("Missing file for {}", parsed.command));
+    }
+    Ok(parsed)
+}
+
+fn read_program(args: &Args) -> Result<(String, Program)> {
+    let path = args.file.as_ref().expect("file is checked in parse_args");
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        QuasarError::RuntimeError(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    let program = parse_program(&source)?;
+    Ok((source, program))
+}
+
+fn build_registry(args: &Args) -> Result<Arc<FunctionRegistry>> {
+    let mut registry = FunctionRegistry::with_builtins();
+    if let Some(path) = &args.policy {
+        RuntimeConfig::load(path)?.apply(&mut registry)?;
+    }
+    Ok(Arc::new(registry))
+}
+
+fn execution_options(args: &Args) -> ExecutionOptions {
+    let options = ExecutionOptions::new(!args.auto_approve).with_speculation(args.speculate);
+    match args.fan_out {
+        Some(limit) => options.with_fan_out(limit),
+        None => options,
+    }
+}
+
+fn prepare_state(args: &Args, options: ExecutionOptions) -> Result<ExecutionState> {
+    let (_, program) = read_program(args)?;
+    let registry = build_registry(args)?;
+    check_program(&program, &registry)?;
+    
+    let inputs = read_inputs(args, &program)?;
+    let mut state = ExecutionState::with_registry(program, registry).with_options(options);
+    state.bind_inputs(inputs)?;
+    Ok(state)
+}
+
+fn cancel_on_ctrl_c(options: &ExecutionOptions) {
+    let cancellation = options.cancellation.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            cancellation.cancel();
+        }
+    });
+}
+
+pub fn exit_code(status: ExecutionStatus) -> i32 {
+    match status {
+        ExecutionStatus::Completed => EXIT_COMPLETED,
+        ExecutionStatus::Cancelled => EXIT_CANCELLED,
+        ExecutionStatus::Stuck | ExecutionStatus::Running => EXIT_STUCK,
+    }
+}
+
+async fn run_file(args: &Args) -> Result<i32> {
+    let mut options = execution_options(args).with_quiet(args.json);
+    if let Some(path) = &args.trace {
+        options = options.with_trace(Arc::new(Trace::to_file(path)?));
+    }
+    cancel_on_ctrl_c(&options);
+    
+    let mut state = prepare_state(args, options)?;
+    run(&mut state).await?;
+    
+    if args.json {
+        let report = serde_json::to_string_pretty(&state.report()).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to serialize result: {}", e))
+        })?;
+        println!("{}", report);
+    }
+    
+    Ok(exit_code(state.status))
+}
+
+async fn debug_file(args: &Args) -> Result<i32> {
+    let mut options = execution_options(args).with_quiet(true);
+    if let Some(path) = &args.trace {
+        options = options.with_trace(Arc::new(Trace::to_file(path)?));
+    }
+    
+    let state = prepare_state(args, options)?;
+    let mut debugger = Debugger::new(Stepper::from_state(state));
+    for breakpoint in &args.breakpoints {
+        let id = debugger.add_breakpoint(breakpoint.clone());
+        println!("Breakpoint {}: {}", id, breakpoint);
+    }
+    
+    let status = run_debugger(&mut debugger).await?;
+    Ok(exit_code(status))
+}
+
+fn read_inputs(args: &Args, program: &Program) -> Result<HashMap<String, Value>> {
+    let mut inputs = HashMap::new();
+    if let Some(path) = &args.inputs_file {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let json: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&text).map_err(|e| {
+            QuasarError::InputError(format!("{} is not a JSON object: {}", path.display(), e))
+        })?;
+        inputs = inputs_from_json(program, &json)?;
+    }
+    
+    for (name, text) in &args.inputs {
+        let bare = || Value::Primitive(PrimitiveValue::String(text.clone()));
+        let declared = program.inputs.iter().find(|input| input.name == *name).map(|input| &input.value_type);
+        let value = match (declared, parse_value(text)) {
+            (Some(ValueType::String), Ok(value @ Value::Primitive(PrimitiveValue::String(_)))) => value,
+            (Some(ValueType::String), _) | (_, Err(_)) => bare(),
+            (_, Ok(value)) => value,
+        };
+        inputs.insert(name.clone(), value);
+    }
+    
+    Ok(inputs)
+}
+
+fn check_file(args: &Args) -> Result<i32> {
+    let (_, program) = read_program(args)?;
+    check_program(&program, &FunctionRegistry::with_builtins())?;
+    println!("{}: ok", args.file.as_ref().unwrap().display());
+    Ok(EXIT_COMPLETED)
+}
+
+fn format_file(args: &Args) -> Result<i32> {
+    let (source, _) = read_program(args)?;
+    let path = args.file.as_ref().unwrap();
+    let (program, trivia) = parse_program_with_trivia(&source)?;
+    let formatted = format!("{}\n", print_program_with_trivia(&program, &trivia));
+    
+    if args.check {
+        if formatted != source {
+            eprintln!("{} is not formatted", path.display());
+            return Ok(EXIT_ERROR);
+        }
+    } else if args.write {
+        std::fs::write(path, &formatted).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to write {}: {}", path.display(), e))
+        })?;
+    } else {
+        print!("{}", formatted);
+    }
+    Ok(EXIT_COMPLETED)
+}
+
+fn graph_file(args: &Args) -> Result<i32> {
+    let (_, program) = read_program(args)?;
+    let graph = DependencyGraph::build(&program);
+    match args.format.as_deref().unwrap_or("dot") {
+        "mermaid" => println!("{}", graph.to_mermaid()),
+        "json" => {
+            let json = serde_json::to_string_pretty(&graph).map_err(|e| {
+                QuasarError::RuntimeError(format!("Failed to serialize graph: {}", e))
+            })?;
+            println!("{}", json);
+        }
+        _ => println!("{}", graph.to_dot()),
+    }
+    Ok(EXIT_COMPLETED)
+}
+
+fn plan_file(args: &Args) -> Result<i32> {
+    let (_, program) = read_program(args)?;
+    let registry = build_registry(args)?;
+    check_program(&program, &registry)?;
+    let lengths = args.lengths.iter().cloned().collect();
+    let report = ParallelismReport::analyze(&program, &registry, &lengths);
+    
+    if args.json {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to serialize report: {}", e))
+        })?;
+        println!("{}", json);
+        return Ok(EXIT_COMPLETED);
+    }
+    
+    println!("{:<16} {:<24} {:>8} {:>10}", "Function", "Calls", "Expected", "Latency");
+    for estimate in &report.functions {
+        let calls = if estimate.conditional { format!("≤ {}", estimate.calls) } else { estimate.calls.to_string() };
+        let latency = format!("{} ms{}", estimate.latency_ms, if estimate.latency_known { "" } else { "?" });
+        println!("{:<16} {:<24} {:>8} {:>10}", estimate.function, calls, estimate.expected_calls, latency);
+    }
+    println!();
+    for (name, length) in &report.lengths {
+        println!("Assuming |{}| = {}", name, length);
+    }
+    let layer: Vec<String> = report.widest_layer.iter().map(|(function, n)| format!("{} × {}", function, n)).collect();
+    println!("Total calls: {}", report.total_calls());
+    println!("Widest parallel layer: {} ({})", report.max_concurrency, layer.join(", "));
+    println!(
+        "Critical path: {} ms, sequential: {} ms, speedup {:.2}x",
+        report.critical_path_ms, report.sequential_ms, report.speedup()
+    );
+    Ok(EXIT_COMPLETED)
+}
+
+async fn start_repl(args: &Args) -> Result<i32> {
+    let registry = build_registry(args)?;
+    Repl::new(registry, execution_options(args)).run().await?;
+    Ok(EXIT_COMPLETED)
+}
+
+async fn serve_dap(args: &Args) -> Result<i32> {
+    let registry = build_registry(args)?;
+    DapServer::new(registry).run().await
+}
+
+async fn serve_lsp() -> Result<i32> {
+    LspServer::new(Arc::new(FunctionRegistry::with_builtins())).run().await
+}
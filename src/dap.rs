@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::{json, Value as Json};
+use quasar::ast::*;
+use quasar::interpreter::{check_program, Breakpoint, Debugger, StopReason, Stepper};
+use quasar::runtime::{inputs_from_json, ExecutionOptions, ExecutionState, FunctionRegistry, QuasarError, Result};
+use quasar::syntax::{parse_program_with_symbols, parse_value, print_conform_value, print_statement, SymbolKind};
+use crate::cli::{exit_code, EXIT_COMPLETED, EXIT_ERROR};
+use crate::wire::{spawn_stdin_reader, write_message};
+
+const MAIN_THREAD: i64 = 1;
+const SCOPE_REFERENCE: i64 = 1;
+const PENDING_REFERENCE: i64 = 2;
+
+pub struct DapServer {
+    registry: Arc<FunctionRegistry>,
+    seq: i64,
+    debugger: Option<Debugger>,
+    source: Option<String>,
+    lines: HashMap<String, usize>,
+    stop_on_entry: bool,
+    line_breakpoints: Vec<usize>,
+    function_breakpoints: Vec<usize>,
+    exception_breakpoints: Vec<usize>,
+}
+
+impl DapServer {
+    pub fn new(registry: Arc<FunctionRegistry>) -> Self {
+        DapServer {
+            registry,
+            seq: 0,
+            debugger: None,
+            source: None,
+            lines: HashMap::new(),
+            stop_on_entry: false,
+            line_breakpoints: Vec::new(),
+            function_breakpoints: Vec::new(),
+            exception_breakpoints: Vec::new(),
+        }
+    }
+
+    pub async fn run(mut self) -> Result<i32> {
+        let mut requests = spawn_stdin_reader();
+        while let Some(request) = requests.recv().await {
+            if request["type"] != "request" {
+                continue;
+            }
+            let command = request["command"].as_str().unwrap_or_default().to_string();
+            let arguments = request.get("arguments").cloned().unwrap_or(Json::Null);
+            
+            let result = self.handle(&command, &arguments).map_err(|e| e.to_string());
+            let succeeded = result.is_ok();
+            self.respond(&request, result)?;
+            if !succeeded {
+                continue;
+            }
+            
+            let action = match command.as_str() {
+                "launch" => self.event("initialized", Json::Null),
+                "configurationDone" if self.stop_on_entry => self.stopped("entry", None),
+                "configurationDone" | "continue" => self.resume().await,
+                "next" | "stepIn" | "stepOut" => self.step().await,
+                "disconnect" | "terminate" => break,
+                _ => Ok(()),
+            };
+            if let Err(e) = action {
+                self.event("output", json!({ "category": "stderr", "output": format!("Error: {}\n", e) }))?;
+                self.event("terminated", Json::Null)?;
+                self.event("exited", json!({ "exitCode": EXIT_ERROR }))?;
+            }
+        }
+        
+        Ok(self.debugger.as_ref().map_or(EXIT_COMPLETED, |debugger| exit_code(debugger.state().status)))
+    }
+
+    fn handle(&mut self, command: &str, arguments: &Json) -> Result<Json> {
+        match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsFunctionBreakpoints": true,
+                "supportsSetVariable": true,
+                "supportsEvaluateForHovers": true,
+                "exceptionBreakpointFilters": [
+                    { "filter": "uncertain", "label": "Uncertain values", "default": false },
+                ],
+            })),
+            "launch" => {
+                self.launch(arguments)?;
+                Ok(Json::Null)
+            }
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "setFunctionBreakpoints" => self.set_function_breakpoints(arguments),
+            "setExceptionBreakpoints" => {
+                let uncertain = arguments["filters"].as_array()
+                    .is_some_and(|filters| filters.iter().any(|filter| filter == "uncertain"));
+                let breakpoints = if uncertain { vec![Breakpoint::Uncertain] } else { Vec::new() };
+                let old = std::mem::take(&mut self.exception_breakpoints);
+                self.exception_breakpoints = self.replace_breakpoints(&old, breakpoints)?;
+                Ok(Json::Null)
+            }
+            "configurationDone" | "disconnect" | "terminate" | "next" | "stepIn" | "stepOut" => Ok(Json::Null),
+            "continue" => Ok(json!({ "allThreadsContinued": true })),
+            "threads" => Ok(json!({ "threads": self.threads()? })),
+            "stackTrace" => {
+                let frames = self.stack_frames(arguments["threadId"].as_i64().unwrap_or(MAIN_THREAD))?;
+                Ok(json!({ "totalFrames": frames.len(), "stackFrames": frames }))
+            }
+            "scopes" => Ok(json!({
+                "scopes": [
+                    { "name": "Scope", "variablesReference": SCOPE_REFERENCE, "expensive": false },
+                    { "name": "Pending calls", "variablesReference": PENDING_REFERENCE, "expensive": false },
+                ],
+            })),
+            "variables" => {
+                let variables = match arguments["variablesReference"].as_i64() {
+                    Some(SCOPE_REFERENCE) => self.scope_variables()?,
+                    Some(PENDING_REFERENCE) => self.pending_variables()?,
+                    _ => Vec::new(),
+                };
+                Ok(json!({ "variables": variables }))
+            }
+            "setVariable" => {
+                let name = arguments["name"].as_str().unwrap_or_default();
+                let value = ConformValue::certain(parse_value(arguments["value"].as_str().unwrap_or_default())?);
+                let printed = print_conform_value(&value);
+                self.debugger_mut()?.set_var(name, value)?;
+                Ok(json!({ "value": printed }))
+            }
+            "evaluate" => {
+                let expression = arguments["expression"].as_str().unwrap_or_default().trim();
+                let value = self.debugger()?.state().lookup_var(expression)
+                    .ok_or_else(|| QuasarError::VariableNotFound(expression.to_string()))?;
+                Ok(json!({ "result": print_conform_value(value), "variablesReference": 0 }))
+            }
+            _ => Err(QuasarError::InvalidOperation(format!("Unsupported request: {}", command))),
+        }
+    }
+
+    fn launch(&mut self, arguments: &Json) -> Result<()> {
+        self.debugger = None;
+        self.source = None;
+        self.lines.clear();
+        self.stop_on_entry = false;
+        self.line_breakpoints.clear();
+        self.function_breakpoints.clear();
+        self.exception_breakpoints.clear();
+        
+        let path = arguments["program"].as_str().ok_or_else(|| {
+            QuasarError::InvalidOperation("launch requires a program path".to_string())
+        })?;
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to read {}: {}", path, e))
+        })?;
+        let (program, symbols) = parse_program_with_symbols(&source)?;
+        check_program(&program, &self.registry)?;
+        
+        let inputs = match arguments["inputs"].as_object() {
+            Some(json) => inputs_from_json(&program, json)?,
+            None => HashMap::new(),
+        };
+        let mut options = ExecutionOptions::new(false)
+            .with_quiet(true)
+            .with_speculation(arguments["speculate"].as_bool().unwrap_or(false));
+        if let Some(limit) = arguments["fanOut"].as_u64() {
+            options = options.with_fan_out(limit as usize);
+        }
+        let mut state = ExecutionState::with_registry(program, self.registry.clone()).with_options(options);
+        state.bind_inputs(inputs)?;
+        
+        for symbol in symbols.into_iter().filter(|symbol| symbol.kind == SymbolKind::Definition) {
+            self.lines.entry(symbol.name).or_insert(symbol.line);
+        }
+        self.source = Some(path.to_string());
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.debugger = Some(Debugger::new(Stepper::from_state(state)));
+        Ok(())
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json> {
+        let requested: Vec<usize> = arguments["breakpoints"].as_array().into_iter().flatten()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .map(|line| line as usize)
+            .collect();
+        
+        let mut breakpoints = Vec::new();
+        let mut verified = Vec::new();
+        for line in requested {
+            let variable = self.lines.iter()
+                .filter(|(_, defined)| **defined == line)
+                .map(|(name, _)| name.clone())
+                .min();
+            match variable {
+                Some(variable) => {
+                    breakpoints.push(Breakpoint::Bind(variable));
+                    verified.push(json!({ "verified": true, "line": line }));
+                }
+                None => verified.push(json!({
+                    "verified": false,
+                    "line": line,
+                    "message": "No assignment on this line",
+                })),
+            }
+        }
+        
+        let old = std::mem::take(&mut self.line_breakpoints);
+        self.line_breakpoints = self.replace_breakpoints(&old, breakpoints)?;
+        Ok(json!({ "breakpoints": verified }))
+    }
+
+    fn set_function_breakpoints(&mut self, arguments: &Json) -> Result<Json> {
+        let names: Vec<String> = arguments["breakpoints"].as_array().into_iter().flatten()
+            .filter_map(|breakpoint| breakpoint["name"].as_str())
+            .map(str::to_string)
+            .collect();
+        
+        let verified: Vec<Json> = names.iter().map(|name| match self.registry.signature(name) {
+            Some(_) => json!({ "verified": true }),
+            None => json!({ "verified": false, "message": format!("Unknown function: {}", name) }),
+        }).collect();
+        
+        let old = std::mem::take(&mut self.function_breakpoints);
+        self.function_breakpoints = self.replace_breakpoints(&old, names.into_iter().map(Breakpoint::Dispatch).collect())?;
+        Ok(json!({ "breakpoints": verified }))
+    }
+
+    fn replace_breakpoints(&mut self, old: &[usize], breakpoints: Vec<Breakpoint>) -> Result<Vec<usize>> {
+        let debugger = self.debugger_mut()?;
+        for id in old {
+            debugger.remove_breakpoint(*id);
+        }
+        Ok(breakpoints.into_iter().map(|breakpoint| debugger.add_breakpoint(breakpoint)).collect())
+    }
+
+    fn threads(&self) -> Result<Vec<Json>> {
+        let state = self.debugger()?.state();
+        let mut threads = vec![json!({ "id": MAIN_THREAD, "name": "main" })];
+        for call in &state.pending_calls {
+            if let Some(id) = thread_id(&call.id) {
+                threads.push(json!({
+                    "id": id,
+                    "name": format!("{} {} = {}({})", call.id, call.assignment_var, call.function, print_conform_value(&call.argument)),
+                }));
+            }
+        }
+        Ok(threads)
+    }
+
+    fn stack_frames(&self, thread: i64) -> Result<Vec<Json>> {
+        let state = self.debugger()?.state();
+        let pending = state.pending_calls.iter().find(|call| thread_id(&call.id) == Some(thread));
+        let frames = state.program.statements.iter().enumerate()
+            .filter(|(_, stmt)| pending.is_none_or(|call| stmt.variable == call.assignment_var))
+            .map(|(index, stmt)| {
+                let name = print_statement(stmt).lines().next().unwrap_or_default().to_string();
+                let mut frame = json!({
+                    "id": index + 1,
+                    "name": name,
+                    "line": self.lines.get(&stmt.variable).copied().unwrap_or(0),
+                    "column": 1,
+                });
+                if let Some(path) = &self.source {
+                    frame["source"] = json!({ "path": path });
+                }
+                frame
+            })
+            .collect();
+        Ok(frames)
+    }
+
+    fn scope_variables(&self) -> Result<Vec<Json>> {
+        let state = self.debugger()?.state();
+        let mut names: Vec<&String> = state.scope.keys().collect();
+        names.sort();
+        Ok(names.into_iter().map(|name| {
+            let value = &state.scope[name];
+            json!({
+                "name": name,
+                "value": print_conform_value(value),
+                "type": ValueType::of_conform(value).to_string(),
+                "variablesReference": 0,
+            })
+        }).collect())
+    }
+
+    fn pending_variables(&self) -> Result<Vec<Json>> {
+        let state = self.debugger()?.state();
+        Ok(state.pending_calls.iter().map(|call| json!({
+            "name": call.assignment_var,
+            "value": format!("{}({}) {}", call.function, print_conform_value(&call.argument), call.id),
+            "variablesReference": 0,
+        })).collect())
+    }
+
+    async fn resume(&mut self) -> Result<()> {
+        let stop = self.debugger_mut()?.resume().await?;
+        self.report(stop)
+    }
+
+    async fn step(&mut self) -> Result<()> {
+        let stop = self.debugger_mut()?.step().await?;
+        self.report(stop)
+    }
+
+    fn report(&mut self, stop: StopReason) -> Result<()> {
+        match stop {
+            StopReason::Step(_) => self.stopped("step", None),
+            StopReason::Breakpoint { id, breakpoint, variable } => {
+                let reason = match breakpoint {
+                    Breakpoint::Dispatch(_) => "function breakpoint",
+                    Breakpoint::Bind(_) => "breakpoint",
+                    Breakpoint::Uncertain => "exception",
+                };
+                self.stopped(reason, Some((id, format!("{} ({})", variable, breakpoint))))
+            }
+            StopReason::Finished(status) => {
+                self.event("terminated", Json::Null)?;
+                self.event("exited", json!({ "exitCode": exit_code(status) }))
+            }
+        }
+    }
+
+    fn stopped(&mut self, reason: &str, hit: Option<(usize, String)>) -> Result<()> {
+        let mut body = json!({ "reason": reason, "threadId": MAIN_THREAD, "allThreadsStopped": true });
+        if let Some((id, description)) = hit {
+            body["hitBreakpointIds"] = json!([id]);
+            body["description"] = json!(description);
+        }
+        self.event("stopped", body)
+    }
+
+    fn debugger(&self) -> Result<&Debugger> {
+        self.debugger.as_ref().ok_or_else(|| QuasarError::InvalidOperation("No program has been launched".to_string()))
+    }
+
+    fn debugger_mut(&mut self) -> Result<&mut Debugger> {
+        self.debugger.as_mut().ok_or_else(|| QuasarError::InvalidOperation("No program has been launched".to_string()))
+    }
+
+    fn respond(&mut self, request: &Json, result: std::result::Result<Json, String>) -> Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(Json::Null) => {}
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response)
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> Result<()> {
+        let mut message = json!({ "type": "event", "event": event });
+        if !body.is_null() {
+            message["body"] = body;
+        }
+        self.send(message)
+    }
+
+    fn send(&mut self, mut message: Json) -> Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut std::io::stdout().lock(), &message).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to write DAP message: {}", e))
+        })
+    }
+}
+
+fn thread_id(call_id: &str) -> Option<i64> {
+    call_id.strip_prefix("?S")?.parse::<i64>().ok().map(|n| n + MAIN_THREAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: &str = "x = 1;\ny = (x, x);\nz = proj 0 y;\nreturn z\n";
+
+    fn launch(server: &mut DapServer, name: &str, source: &str) -> Result<Json> {
+        let path = std::env::temp_dir().join(format!("quasar-dap-{}-{}.qsr", name, std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let result = server.handle("launch", &json!({ "program": path }));
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    fn server() -> DapServer {
+        DapServer::new(Arc::new(FunctionRegistry::with_builtins()))
+    }
+
+    #[tokio::test]
+    async fn line_breakpoints_stop_at_assignments() {
+        let mut server = server();
+        launch(&mut server, "lines", PROGRAM).unwrap();
+        
+        let body = server.handle("setBreakpoints", &json!({ "breakpoints": [{ "line": 2 }, { "line": 4 }] })).unwrap();
+        assert_eq!(body["breakpoints"][0]["verified"], true);
+        assert_eq!(body["breakpoints"][1]["verified"], false);
+        
+        let stop = server.debugger_mut().unwrap().resume().await.unwrap();
+        assert!(matches!(stop, StopReason::Breakpoint { breakpoint: Breakpoint::Bind(variable), .. } if variable == "y"));
+        let body = server.handle("evaluate", &json!({ "expression": "x" })).unwrap();
+        assert_eq!(body["result"], "1");
+        
+        let frames = server.handle("stackTrace", &json!({ "threadId": MAIN_THREAD })).unwrap();
+        assert_eq!(frames["stackFrames"][0]["line"], 3);
+    }
+
+    #[test]
+    fn function_breakpoints_verify_function_names() {
+        let mut server = server();
+        launch(&mut server, "functions", PROGRAM).unwrap();
+        
+        let body = server.handle("setFunctionBreakpoints", &json!({ "breakpoints": [{ "name": "find" }, { "name": "missing" }] })).unwrap();
+        assert_eq!(body["breakpoints"][0]["verified"], true);
+        assert_eq!(body["breakpoints"][1]["verified"], false);
+        assert_eq!(server.debugger().unwrap().breakpoints().len(), 2);
+    }
+
+    #[test]
+    fn launch_resets_the_previous_program() {
+        let mut server = server();
+        launch(&mut server, "first", PROGRAM).unwrap();
+        server.handle("setBreakpoints", &json!({ "breakpoints": [{ "line": 1 }] })).unwrap();
+        
+        launch(&mut server, "second", "a = 1;\nreturn a\n").unwrap();
+        assert!(server.debugger().unwrap().breakpoints().is_empty());
+        assert_eq!(server.lines, HashMap::from([("a".to_string(), 1)]));
+        
+        assert!(launch(&mut server, "broken", "a = ;\nreturn a\n").is_err());
+        assert!(server.debugger().is_err());
+        assert!(server.handle("threads", &Json::Null).is_err());
+    }
+
+    #[test]
+    fn pending_calls_map_to_threads() {
+        assert_eq!(thread_id("?S1"), Some(2));
+        assert_eq!(thread_id("?S12"), Some(13));
+        assert_eq!(thread_id("x"), None);
+    }
+}
@@ -0,0 +1,126 @@
+use quasar::ast::*;
+use quasar::interpreter::{Breakpoint, Debugger, StopReason};
+use quasar::runtime::{ExecutionStatus, QuasarError, Result};
+use quasar::syntax::{parse_value, print_conform_value, print_statement};
+use crate::repl::{print_pending, print_scope, print_step, read_line};
+
+const HELP: &str = "\
+  continue, c              run until a breakpoint is hit or execution ends
+  step, s                  apply a single rewrite
+  break dispatch <f>       pause before a call to <f> is dispatched
+  break bind <x>           pause when <x> becomes bound
+  break uncertain          pause when a variable is bound to more than one possible value
+  delete <id>              remove a breakpoint
+  breakpoints              list breakpoints
+  print <x>, p <x>         show the value of a variable
+  set <x> = <literal>      replace the value of a bound variable
+  scope                    show all bound variables
+  pending                  show pending external calls
+  program                  show statements that have not been reduced yet
+  help                     show this help
+  quit, q                  stop debugging
+";
+
+pub async fn run_debugger(debugger: &mut Debugger) -> Result<ExecutionStatus> {
+    println!("QUASAR debugger. Type help for commands.");
+    
+    loop {
+        let Some(line) = read_line("(qdb) ").await? else {
+            break;
+        };
+        match command(debugger, line.trim()).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+    
+    Ok(debugger.state().status)
+}
+
+async fn command(debugger: &mut Debugger, line: &str) -> Result<bool> {
+    let (name, rest) = line.split_once(' ').map_or((line, ""), |(name, rest)| (name, rest.trim()));
+    
+    match name {
+        "" => {}
+        "quit" | "q" => return Ok(false),
+        "help" => print!("{}", HELP),
+        "continue" | "c" => {
+            let stop = debugger.resume().await?;
+            report(debugger, stop);
+        }
+        "step" | "s" => {
+            let stop = debugger.step().await?;
+            report(debugger, stop);
+        }
+        "break" | "b" => {
+            let breakpoint = parse_breakpoint(rest)?;
+            println!("Breakpoint {}: {}", debugger.add_breakpoint(breakpoint.clone()), breakpoint);
+        }
+        "delete" => {
+            let id = rest.parse().map_err(|_| QuasarError::InvalidOperation(format!("Invalid breakpoint id: {}", rest)))?;
+            if !debugger.remove_breakpoint(id) {
+                println!("No breakpoint {}", id);
+            }
+        }
+        "breakpoints" => {
+            for (id, breakpoint) in debugger.breakpoints() {
+                println!("{}: {}", id, breakpoint);
+            }
+        }
+        "print" | "p" => {
+            let value = debugger.state().lookup_var(rest).ok_or_else(|| QuasarError::VariableNotFound(rest.to_string()))?;
+            println!("{} = {}", rest, print_conform_value(value));
+        }
+        "set" => {
+            let (variable, literal) = rest.split_once('=').ok_or_else(|| {
+                QuasarError::InvalidOperation("Usage: set <x> = <literal>".to_string())
+            })?;
+            let value = ConformValue::certain(parse_value(literal.trim())?);
+            debugger.set_var(variable.trim(), value)?;
+        }
+        "scope" => print_scope(debugger.state()),
+        "pending" => print_pending(debugger.state()),
+        "program" => {
+            for stmt in &debugger.state().program.statements {
+                println!("{}", print_statement(stmt));
+            }
+        }
+        _ => println!("Unknown command {}. Type help for commands.", name),
+    }
+    
+    Ok(true)
+}
+
+pub fn parse_breakpoint(spec: &str) -> Result<Breakpoint> {
+    let mut parts = spec.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("dispatch"), Some(function), None) => Ok(Breakpoint::Dispatch(function.to_string())),
+        (Some("bind"), Some(variable), None) => Ok(Breakpoint::Bind(variable.to_string())),
+        (Some("uncertain"), None, None) => Ok(Breakpoint::Uncertain),
+        _ => Err(QuasarError::InvalidOperation(format!(
+            "Invalid breakpoint '{}': expected dispatch <f>, bind <x> or uncertain", spec
+        ))),
+    }
+}
+
+fn report(debugger: &Debugger, stop: StopReason) {
+    match stop {
+        StopReason::Step(step) => print_step(&step),
+        StopReason::Breakpoint { id, breakpoint, variable } => {
+            println!("Breakpoint {} hit ({}) at {}", id, breakpoint, variable);
+            if let Some(value) = debugger.state().lookup_var(&variable) {
+                println!("  {} = {}", variable, print_conform_value(value));
+            } else if let Some(stmt) = debugger.state().program.statements.iter().find(|stmt| stmt.variable == variable) {
+                println!("  {}", print_statement(stmt));
+            }
+        }
+        StopReason::Finished(status) => {
+            let state = debugger.state();
+            println!("Execution finished: {:?}", status);
+            if let Some(value) = state.lookup_var(&state.program.return_var) {
+                println!("{} = {}", state.program.return_var, print_conform_value(value));
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use crate::ast::*;
+use crate::runtime::{FunctionRegistry, Result, QuasarError};
+
+pub fn check_program(program: &Program, registry: &FunctionRegistry) -> Result<()> {
+    infer_types(program, registry, HashMap::new()).map(|_| ())
+}
+
+pub fn infer_types(
+    program: &Program,
+    registry: &FunctionRegistry,
+    mut env: HashMap<String, ValueType>,
+) -> Result<HashMap<String, ValueType>> {
+    for input in &program.inputs {
+        env.insert(input.name.clone(), input.value_type.clone());
+    }
+    check_statements(&program.statements, registry, &mut env)?;
+    if !program.return_var.is_empty() && !env.contains_key(&program.return_var) {
+        return Err(QuasarError::VariableNotFound(program.return_var.clone()));
+    }
+    Ok(env)
+}
+
+fn check_statements(
+    statements: &[Statement],
+    registry: &FunctionRegistry,
+    env: &mut HashMap<String, ValueType>,
+) -> Result<()> {
+    for stmt in statements {
+        env.entry(stmt.variable.clone()).or_insert(ValueType::Any);
+    }
+    for stmt in statements {
+        let ty = check_expression(&stmt.expression, registry, env)?;
+        env.insert(stmt.variable.clone(), ty);
+    }
+    Ok(())
+}
+
+fn check_expression(
+    expr: &Expression,
+    registry: &FunctionRegistry,
+    env: &HashMap<String, ValueType>,
+) -> Result<ValueType> {
+    let lookup = |var: &str| {
+        env.get(var).cloned().ok_or_else(|| QuasarError::VariableNotFound(var.to_string()))
+    };
+    
+    match expr {
+        Expression::Primitive(value) => Ok(ValueType::of(value)),
+        
+        Expression::Variable(var) => lookup(var),
+        
+        Expression::Tuple(vars) => Ok(ValueType::Tuple(vars.iter().map(|v| lookup(v)).collect::<Result<_>>()?)),
+        
+        Expression::ExternalCall { function, argument } => {
+            let signature = registry.signature(function).ok_or_else(|| {
+                QuasarError::ExternalFunctionError(format!("Unknown function: {}", function))
+            })?;
+            let argument_type = lookup(argument)?;
+            if !argument_type.is_compatible_with(&signature.parameter) {
+                return Err(QuasarError::TypeError(format!(
+                    "{} expects an argument of type {}, but `{}` has type {}",
+                    function, signature.parameter, argument, argument_type
+                )));
+            }
+            Ok(signature.return_type)
+        }
+        
+        Expression::Projection { index, variable } => match lookup(variable)? {
+            ValueType::Tuple(elements) => elements.get(*index).cloned().ok_or_else(|| {
+                QuasarError::TypeError(format!("Tuple index {} out of bounds for `{}`", index, variable))
+            }),
+            ValueType::Any => Ok(ValueType::Any),
+            other => Err(QuasarError::TypeError(format!(
+                "Projection can only be applied to tuples, but `{}` has type {}",
+                variable, other
+            ))),
+        },
+        
+        Expression::Fold { list, initial, block } => {
+            let element_type = match lookup(list)? {
+                ValueType::List(element) => *element,
+                _ => ValueType::Any,
+            };
+            let mut accumulator_type = lookup(initial)?;
+            loop {
+                let parameter_type = ValueType::Tuple(vec![accumulator_type.clone(), element_type.clone()]);
+                let result_type = check_block(block, parameter_type, registry, env)?;
+                let joined = accumulator_type.join(&result_type);
+                if joined == accumulator_type {
+                    return Ok(accumulator_type);
+                }
+                accumulator_type = joined;
+            }
+        }
+        
+        Expression::If { condition, then_block, else_block } => {
+            let condition_type = lookup(condition)?;
+            if !matches!(condition_type, ValueType::Boolean | ValueType::Any) {
+                return Err(QuasarError::TypeError(format!(
+                    "if expects a Boolean condition, but `{}` has type {}",
+                    condition, condition_type
+                )));
+            }
+            let then_type = check_block(then_block, ValueType::Tuple(vec![]), registry, env)?;
+            match else_block {
+                Some(else_block) => Ok(then_type.join(&check_block(else_block, ValueType::Tuple(vec![]), registry, env)?)),
+                None => Ok(then_type),
+            }
+        }
+        
+        Expression::Try { body, handler } => {
+            let body_type = check_block(body, ValueType::Tuple(vec![]), registry, env)?;
+            Ok(body_type.join(&check_block(handler, ValueType::String, registry, env)?))
+        }
+        
+        Expression::Catch { value, handler } => {
+            let value_type = lookup(value)?;
+            Ok(value_type.join(&check_block(handler, ValueType::String, registry, env)?))
+        }
+        
+        Expression::Join(vars) => {
+            let mut types = vars.iter().map(|var| lookup(var));
+            let Some(first) = types.next() else {
+                return Ok(ValueType::Any);
+            };
+            types.try_fold(first?, |joined, ty| Ok(joined.join(&ty?)))
+        }
+        
+        Expression::AbstractPrimitive(cvalue) => match cvalue.as_certain() {
+            Some(value) => Ok(ValueType::of(value)),
+            None => Ok(ValueType::Any),
+        },
+        
+        _ => Ok(ValueType::Any),
+    }
+}
+
+fn check_block(
+    block: &Block,
+    parameter_type: ValueType,
+    registry: &FunctionRegistry,
+    env: &HashMap<String, ValueType>,
+) -> Result<ValueType> {
+    let mut block_env = env.clone();
+    block_env.insert(block.parameter.clone(), parameter_type);
+    check_statements(&block.body, registry, &mut block_env)?;
+    block_env.get(&block.return_var).cloned().ok_or_else(|| QuasarError::VariableNotFound(block.return_var.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::parse_program;
+
+    fn check(source: &str) -> Result<()> {
+        check_program(&parse_program(source).unwrap(), &FunctionRegistry::with_builtins())
+    }
+
+    #[test]
+    fn if_condition_must_be_boolean() {
+        assert!(check("c = true; x = if c { return c } else { return c }; return x").is_ok());
+        assert!(matches!(
+            check("c = \"yes\"; x = if c { return c } else { return c }; return x"),
+            Err(QuasarError::TypeError(_))
+        ));
+    }
+
+    #[test]
+    fn undefined_variables_are_errors() {
+        assert!(matches!(check("x = find(y); return x"), Err(QuasarError::VariableNotFound(_))));
+        assert!(matches!(check("x = 1; return y"), Err(QuasarError::VariableNotFound(_))));
+        assert!(check("y = x; x = \"a\"; return y").is_ok());
+    }
+
+    #[test]
+    fn block_results_are_typed() {
+        assert!(matches!(
+            check("c = true; x = if c { r = 1; return r }; y = find(x); return y"),
+            Err(QuasarError::TypeError(_))
+        ));
+        assert!(matches!(
+            check("s = \"a\"; y = try { r = exists(s); return r } else { e => f = exists(e); return f }; z = find(y); return z"),
+            Err(QuasarError::TypeError(_))
+        ));
+        assert!(check("c = true; a = \"a\"; x = if c { return a } else { return a }; y = find(x); return y").is_ok());
+    }
+
+    #[test]
+    fn branch_types_are_joined() {
+        let program = parse_program("c = true; a = 1; b = \"b\"; x = if c { return a } else { return b }; y = join {a, a}; return x").unwrap();
+        let types = infer_types(&program, &FunctionRegistry::with_builtins(), HashMap::new()).unwrap();
+        
+        assert_eq!(types["x"], ValueType::Any);
+        assert_eq!(types["y"], ValueType::Integer);
+    }
+
+    #[test]
+    fn fold_result_joins_initial_and_block_types() {
+        let program = parse_program("s = \"a\"; xs = find(s); n = \"none\"; r = fold xs n {p => e = proj 1 p; return e}; return r").unwrap();
+        let types = infer_types(&program, &FunctionRegistry::with_builtins(), HashMap::new()).unwrap();
+        assert_eq!(types["r"], ValueType::String);
+        
+        let program = parse_program("s = \"a\"; xs = find(s); n = 0; r = fold xs n {p => e = proj 1 p; return e}; return r").unwrap();
+        let types = infer_types(&program, &FunctionRegistry::with_builtins(), HashMap::new()).unwrap();
+        assert_eq!(types["r"], ValueType::Any);
+    }
+
+    #[test]
+    fn sample_type_checks() {
+        assert!(check(include_str!("../../examples/sample.qsr")).is_ok());
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::ast::*;
+use crate::runtime::{call_key, ExecutionState, ExecutionStatus, QuasarError, Result};
+use crate::interpreter::{find_dispatchable_calls, find_speculative_calls, DispatchableCall, Step, Stepper};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Breakpoint {
+    Dispatch(String),
+    Bind(String),
+    Uncertain,
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Dispatch(function) => write!(f, "before dispatching {}", function),
+            Breakpoint::Bind(variable) => write!(f, "when {} is bound", variable),
+            Breakpoint::Uncertain => write!(f, "when a value becomes uncertain"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    Breakpoint {
+        id: usize,
+        breakpoint: Breakpoint,
+        variable: String,
+    },
+    Step(Step),
+    Finished(ExecutionStatus),
+}
+
+pub struct Debugger {
+    stepper: Stepper,
+    breakpoints: Vec<(usize, Breakpoint)>,
+    next_id: usize,
+    announced_dispatches: HashSet<String>,
+    finished: bool,
+}
+
+impl Debugger {
+    pub fn new(stepper: Stepper) -> Self {
+        Debugger {
+            stepper,
+            breakpoints: Vec::new(),
+            next_id: 1,
+            announced_dispatches: HashSet::new(),
+            finished: false,
+        }
+    }
+
+    pub fn state(&self) -> &ExecutionState {
+        self.stepper.state()
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push((id, breakpoint));
+        id
+    }
+
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|(bp_id, _)| *bp_id != id);
+        self.breakpoints.len() != before
+    }
+
+    pub fn breakpoints(&self) -> &[(usize, Breakpoint)] {
+        &self.breakpoints
+    }
+
+    pub fn set_var(&mut self, name: &str, value: ConformValue) -> Result<()> {
+        if self.stepper.state().lookup_var(name).is_none() {
+            return Err(QuasarError::VariableNotFound(name.to_string()));
+        }
+        self.stepper.state_mut().set_var(name.to_string(), value);
+        Ok(())
+    }
+
+    pub async fn step(&mut self) -> Result<StopReason> {
+        if self.finished {
+            return Ok(StopReason::Finished(self.state().status));
+        }
+        match self.stepper.step().await? {
+            Some(step) => Ok(StopReason::Step(step)),
+            None => {
+                self.finished = true;
+                Ok(StopReason::Finished(self.state().status))
+            }
+        }
+    }
+
+    pub async fn resume(&mut self) -> Result<StopReason> {
+        loop {
+            if let Some(stop) = self.check_dispatch() {
+                return Ok(stop);
+            }
+            
+            let bound_before = self.watched_bound();
+            let uncertain_before = self.uncertain_vars();
+            let stop = self.step().await?;
+            if !matches!(stop, StopReason::Step(_)) {
+                return Ok(stop);
+            }
+            
+            if let Some(stop) = self.check_bindings(&bound_before, &uncertain_before) {
+                return Ok(stop);
+            }
+        }
+    }
+
+    /// Announces each distinct call once while it waits for dispatch. Calls are forgotten once
+    /// dispatched, so a later fold iteration making the same call stops again; calls that will
+    /// share an in-flight or completed result are not dispatches and never stop.
+    fn check_dispatch(&mut self) -> Option<StopReason> {
+        let state = self.state();
+        let shared = |call: &DispatchableCall, key: &String| {
+            state.registry.signature(&call.function).is_some_and(|signature| signature.effects.pure)
+                && (state.inflight_calls.contains_key(key) || state.completed_calls.contains_key(key))
+        };
+        let calls: Vec<_> = find_dispatchable_calls(state).into_iter()
+            .chain(find_speculative_calls(state).into_iter().map(|candidate| DispatchableCall {
+                assignment_var: candidate.if_var,
+                ..candidate.call
+            }))
+            .map(|call| (call_key(&call.function, &call.argument), call))
+            .filter(|(key, call)| !shared(call, key))
+            .collect();
+        self.announced_dispatches.retain(|key| calls.iter().any(|(waiting, _)| waiting == key));
+        
+        for (key, call) in calls {
+            let hit = self.breakpoints.iter().find(|(_, bp)| {
+                matches!(bp, Breakpoint::Dispatch(function) if *function == call.function)
+            });
+            if let Some((id, breakpoint)) = hit.cloned() {
+                if self.announced_dispatches.insert(key) {
+                    return Some(StopReason::Breakpoint { id, breakpoint, variable: call.assignment_var });
+                }
+            }
+        }
+        None
+    }
+
+    fn check_bindings(&self, bound_before: &HashSet<String>, uncertain_before: &HashSet<String>) -> Option<StopReason> {
+        let state = self.state();
+        for (id, breakpoint) in &self.breakpoints {
+            let variable = match breakpoint {
+                Breakpoint::Bind(variable) => {
+                    let bound = state.lookup_var(variable).is_some() && !bound_before.contains(variable);
+                    bound.then(|| variable.clone())
+                }
+                Breakpoint::Uncertain => {
+                    let mut newly: Vec<_> = self.uncertain_vars().difference(uncertain_before).cloned().collect();
+                    newly.sort();
+                    newly.into_iter().next()
+                }
+                Breakpoint::Dispatch(_) => None,
+            };
+            if let Some(variable) = variable {
+                return Some(StopReason::Breakpoint { id: *id, breakpoint: breakpoint.clone(), variable });
+            }
+        }
+        None
+    }
+
+    fn watched_bound(&self) -> HashSet<String> {
+        self.breakpoints.iter()
+            .filter_map(|(_, bp)| match bp {
+                Breakpoint::Bind(variable) if self.state().lookup_var(variable).is_some() => Some(variable.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn uncertain_vars(&self) -> HashSet<String> {
+        self.state().scope.iter()
+            .filter(|(_, value)| !value.is_certain())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use crate::runtime::ExecutionOptions;
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::syntax::parse_program;
+
+    fn debugger(source: &str, functions: &[&TestFunction], options: ExecutionOptions) -> Debugger {
+        let stepper = Stepper::new(parse_program(source).unwrap(), Arc::new(registry(functions)), options).unwrap();
+        Debugger::new(stepper)
+    }
+
+    async fn dispatch_stops(debugger: &mut Debugger) -> usize {
+        let mut stops = 0;
+        loop {
+            match debugger.resume().await.unwrap() {
+                StopReason::Breakpoint { breakpoint: Breakpoint::Dispatch(_), .. } => stops += 1,
+                StopReason::Finished(status) => {
+                    assert_eq!(status, ExecutionStatus::Completed);
+                    return stops;
+                }
+                other => panic!("unexpected stop {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatch_breakpoint_fires_for_every_fold_iteration() {
+        let echo = TestFunction::echo("echo");
+        let source = "l = [1, 2]; i = 0; r = fold l i { p => y = echo(p); s = proj 1 y; return s }; return r";
+        let mut debugger = debugger(source, &[&echo], ExecutionOptions::new(false).with_quiet(true));
+        debugger.add_breakpoint(Breakpoint::Dispatch("echo".to_string()));
+        
+        assert_eq!(dispatch_stops(&mut debugger).await, 2);
+        assert_eq!(echo.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn dispatch_breakpoint_fires_for_speculative_calls() {
+        let slow = TestFunction::constant("slow", Value::Primitive(PrimitiveValue::Boolean(true)))
+            .with_delay(Duration::from_millis(50));
+        let echo = TestFunction::echo("echo");
+        let source = "a = \"q\"; c = slow(a); r = if c { y = echo(a); return y } else { return a }; return r";
+        let options = ExecutionOptions::new(false).with_quiet(true).with_speculation(true);
+        let mut debugger = debugger(source, &[&slow, &echo], options);
+        debugger.add_breakpoint(Breakpoint::Dispatch("echo".to_string()));
+        
+        let stop = debugger.resume().await.unwrap();
+        assert!(matches!(stop, StopReason::Breakpoint { variable, .. } if variable == "r"));
+        assert_eq!(debugger.state().speculation.dispatched, 0);
+        
+        assert_eq!(dispatch_stops(&mut debugger).await, 0);
+        assert_eq!(debugger.state().speculation.dispatched, 1);
+        assert_eq!(echo.calls(), 1);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::ast::*;
-use crate::runtime::{ExecutionState, PendingCall, get_external_function, Result, QuasarError};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use crate::runtime::{CancellationToken, ExecutionState, FailureMode, ExternalFunction, PendingCall, RejectedCall, Result, QuasarError, Rule, SpeculativeCall, call_with_policy, call_key, CURRENT_CALL_ID};
 use tokio::task;
 
 #[derive(Debug, Clone)]
@@ -9,12 +12,19 @@ pub struct DispatchableCall {
     pub argument: ConformValue,
 }
 
+#[derive(Debug, Clone)]
+pub struct SpeculationCandidate {
+    pub if_var: String,
+    pub then_branch: bool,
+    pub call: DispatchableCall,
+}
+
 pub fn find_dispatchable_calls(state: &ExecutionState) -> Vec<DispatchableCall> {
     let mut calls = Vec::new();
     
     for stmt in &state.program.statements {
         if let Expression::ExternalCall { function, argument } = &stmt.expression {
-            if let Some(arg_value) = state.lookup_var(argument) {
+            if let Some(arg_value) = state.lookup_var(argument).filter(|value| !state.fans_out(value)) {
                 calls.push(DispatchableCall {
                     assignment_var: stmt.variable.clone(),
                     function: function.clone(),
@@ -27,60 +37,334 @@ pub fn find_dispatchable_calls(state: &ExecutionState) -> Vec<DispatchableCall>
     calls
 }
 
+pub fn find_speculative_calls(state: &ExecutionState) -> Vec<SpeculationCandidate> {
+    let mut candidates = Vec::new();
+    if !state.options.speculate || state.options.with_approval {
+        return candidates;
+    }
+    
+    for stmt in &state.program.statements {
+        let Expression::If { condition, then_block, else_block } = &stmt.expression else {
+            continue;
+        };
+        if state.lookup_var(condition).is_some() {
+            continue;
+        }
+        
+        let branches = std::iter::once((then_block, true)).chain(else_block.iter().map(|block| (block, false)));
+        for (block, then_branch) in branches {
+            let mut local = HashSet::from([block.parameter.clone()]);
+            for inner in &block.body {
+                if let Expression::ExternalCall { function, argument } = &inner.expression {
+                    let pure = state.registry.signature(function).is_some_and(|signature| signature.effects.pure);
+                    let value = state.lookup_var(argument)
+                        .filter(|value| pure && !local.contains(argument) && value.failure().is_none() && !state.fans_out(value));
+                    if let Some(value) = value {
+                        candidates.push(SpeculationCandidate {
+                            if_var: stmt.variable.clone(),
+                            then_branch,
+                            call: DispatchableCall {
+                                assignment_var: reserved_name(&stmt.variable, "speculative"),
+                                function: function.clone(),
+                                argument: value.clone(),
+                            },
+                        });
+                    }
+                }
+                local.insert(inner.variable.clone());
+            }
+        }
+    }
+    
+    candidates
+}
+
+pub fn dispatch_speculative_calls(state: &mut ExecutionState, candidates: Vec<SpeculationCandidate>) -> Result<bool> {
+    let mut dispatched = false;
+    for candidate in candidates {
+        let key = call_key(&candidate.call.function, &candidate.call.argument);
+        if let Some(speculative) = state.speculative_calls.get_mut(&key) {
+            if speculative.if_var == candidate.if_var {
+                speculative.then_branch |= candidate.then_branch;
+                speculative.else_branch |= !candidate.then_branch;
+            }
+            continue;
+        }
+        if state.inflight_calls.contains_key(&key) || state.completed_calls.contains_key(&key) {
+            continue;
+        }
+        if state.result_cache().is_some_and(|c| c.get(&candidate.call.function, &candidate.call.argument).is_some()) {
+            continue;
+        }
+        let Ok(func) = lookup_function(state, &candidate.call) else {
+            continue;
+        };
+        
+        let call_id = state.generate_call_id();
+        state.log(&format!("Speculatively dispatching {} call {} for {}", candidate.call.function, call_id, candidate.if_var));
+        spawn_call(state, call_id.clone(), &candidate.call, func);
+        state.speculative_calls.insert(key, SpeculativeCall {
+            id: call_id,
+            if_var: candidate.if_var.clone(),
+            then_branch: candidate.then_branch,
+            else_branch: !candidate.then_branch,
+        });
+        state.speculation.dispatched += 1;
+        dispatched = true;
+        
+        if let Some(stmt) = state.program.statements.iter().find(|stmt| stmt.variable == candidate.if_var).cloned() {
+            state.record_reduction(Rule::Spec, &stmt, std::slice::from_ref(&stmt))?;
+        }
+    }
+    
+    Ok(dispatched)
+}
+
 pub async fn dispatch_calls(
     state: &mut ExecutionState,
     calls: Vec<DispatchableCall>,
     with_approval: bool,
 ) -> Result<()> {
     for call in calls {
+        if state.is_cancelled() {
+            break;
+        }
+        
+        if let Some(error) = call.argument.failure() {
+            let value = ConformValue::certain(error.clone());
+            replace_call_statement(state, &call.assignment_var, Expression::AbstractPrimitive(value), Rule::Err)?;
+            continue;
+        }
+        
+        let func = match lookup_function(state, &call) {
+            Ok(func) => func,
+            Err(e) => {
+                let value = state.handle_call_failure(std::slice::from_ref(&call.assignment_var), &call.function, &call.argument, e)?;
+                replace_call_statement(state, &call.assignment_var, Expression::AbstractPrimitive(value), Rule::Err)?;
+                continue;
+            }
+        };
+        let signature = func.signature();
+        if state.options.failure_mode == FailureMode::BindUnknown {
+            state.unknown_result(&call.function)?;
+        }
+        let shared = signature.effects.pure;
+        let key = call_key(&call.function, &call.argument);
+        
+        if shared {
+            if let Some(value) = state.completed_calls.get(&key).cloned() {
+                state.claim_speculation(&key, &call.assignment_var);
+                state.log(&format!("Reusing result of identical {} call for {}", call.function, call.assignment_var));
+                replace_call_statement(state, &call.assignment_var, Expression::AbstractPrimitive(value), Rule::Disp)?;
+                continue;
+            }
+            if let Some(call_id) = state.inflight_calls.get(&key).cloned() {
+                state.claim_speculation(&key, &call.assignment_var);
+                state.log(&format!("Sharing in-flight {} call {} with {}", call.function, call_id, call.assignment_var));
+                replace_call_statement(state, &call.assignment_var, Expression::PendingCall(call_id), Rule::Disp)?;
+                continue;
+            }
+        }
+        
+        if let Some(value) = state.result_cache().and_then(|c| c.get(&call.function, &call.argument)) {
+            state.log(&format!("Using cached result of {} for {}", call.function, call.assignment_var));
+            if let Some(recorder) = state.options.recorder.clone() {
+                let call_id = state.generate_call_id();
+                recorder.record(&call_id, &call.function, &call.argument, &Ok(value.clone()))?;
+            }
+            replace_call_statement(state, &call.assignment_var, Expression::AbstractPrimitive(value), Rule::Disp)?;
+            continue;
+        }
+        
         if with_approval {
-            println!("\n=== External Call Request ===");
-            println!("Function: {}", call.function);
-            println!("Argument: {:?}", call.argument);
-            println!("Approve? (y/n): ");
+            let request = format!(
+                "\n=== External Call Request ===\nFunction: {}\nArgument: {:?}\nApprove? (y/n): ",
+                call.function, call.argument
+            );
+            if state.options.quiet {
+                eprintln!("{}", request);
+            } else {
+                println!("{}", request);
+            }
             
-            use tokio::io::{AsyncBufReadExt, BufReader};
-            let stdin = tokio::io::stdin();
-            let mut reader = BufReader::new(stdin);
-            let mut input = String::new();
-            reader.read_line(&mut input).await.map_err(|e| {
-                QuasarError::RuntimeError(format!("Failed to read input: {}", e))
-            })?;
+            let read = task::spawn_blocking(|| {
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input).map(|_| input)
+            });
+            let cancellation = state.options.cancellation.clone();
+            let Some(input) = await_approval(read, &cancellation).await? else {
+                state.log("Approval prompt cancelled");
+                return Ok(());
+            };
             
             if !input.trim().eq_ignore_ascii_case("y") {
-                println!("Call rejected by user");
+                state.log("Call rejected by user");
+                state.rejected.push(RejectedCall {
+                    assignment_var: call.assignment_var.clone(),
+                    function: call.function.clone(),
+                    argument: call.argument.clone(),
+                });
+                let value = ConformValue::certain(Value::Rejected(call.function.clone()));
+                if shared {
+                    state.completed_calls.insert(key, value.clone());
+                }
+                replace_call_statement(state, &call.assignment_var, Expression::AbstractPrimitive(value), Rule::Disp)?;
                 continue;
             }
         }
         
         let call_id = state.generate_call_id();
+        spawn_call(state, call_id.clone(), &call, func);
         
-        let function_name = call.function.clone();
-        let argument = call.argument.clone();
-        
-        let handle = task::spawn(async move {
-            if let Some(func) = get_external_function(&function_name) {
-                func.call(&argument).await
-            } else {
-                Err(QuasarError::ExternalFunctionError(
-                    format!("Unknown function: {}", function_name)
-                ))
-            }
+        replace_call_statement(state, &call.assignment_var, Expression::PendingCall(call_id), Rule::Disp)?;
+    }
+    
+    Ok(())
+}
+
+async fn await_approval(
+    read: impl Future<Output = std::result::Result<std::io::Result<String>, task::JoinError>>,
+    cancellation: &CancellationToken,
+) -> Result<Option<String>> {
+    tokio::select! {
+        input = read => input
+            .map_err(|e| QuasarError::RuntimeError(format!("Failed to read input: {}", e)))?
+            .map(Some)
+            .map_err(|e| QuasarError::RuntimeError(format!("Failed to read input: {}", e))),
+        _ = cancellation.cancelled() => Ok(None),
+    }
+}
+
+pub fn spawn_call(
+    state: &mut ExecutionState,
+    call_id: String,
+    call: &DispatchableCall,
+    func: Arc<dyn ExternalFunction>,
+) {
+    let signature = func.signature();
+    let shared = signature.effects.pure;
+    let argument = call.argument.clone();
+    let policy = state.registry.policy(&call.function);
+    let scheduler = state.registry.scheduler();
+    
+    let handle = task::spawn(CURRENT_CALL_ID.scope(call_id.clone(), async move {
+        let result = call_with_policy(func, &argument, &policy, &scheduler).await?;
+        signature.check_return(&result)?;
+        Ok(result)
+    }));
+    
+    if shared {
+        state.inflight_calls.insert(call_key(&call.function, &call.argument), call_id.clone());
+    }
+    
+    state.pending_calls.push(PendingCall {
+        id: call_id,
+        assignment_var: call.assignment_var.clone(),
+        function: call.function.clone(),
+        argument: call.argument.clone(),
+        handle,
+    });
+}
+
+fn lookup_function(state: &ExecutionState, call: &DispatchableCall) -> Result<Arc<dyn ExternalFunction>> {
+    let func = state.registry.get(&call.function).ok_or_else(|| {
+        QuasarError::ExternalFunctionError(format!("Unknown function: {}", call.function))
+    })?;
+    func.signature().check_argument(&call.argument)?;
+    Ok(func)
+}
+
+fn replace_call_statement(
+    state: &mut ExecutionState,
+    assignment_var: &str,
+    expression: Expression,
+    rule: Rule,
+) -> Result<()> {
+    let Some(stmt) = state.program.statements.iter_mut().find(|stmt| {
+        stmt.variable == assignment_var && matches!(stmt.expression, Expression::ExternalCall { .. })
+    }) else {
+        return Ok(());
+    };
+    
+    let before = stmt.clone();
+    stmt.expression = expression;
+    let after = stmt.clone();
+    state.record_reduction(rule, &before, &[after])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use crate::interpreter::execute_with_inputs;
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::runtime::{ExecutionOptions, ExecutionStatus};
+    use crate::syntax::parse_program;
+
+    #[tokio::test]
+    async fn cancellation_interrupts_approval_prompt() {
+        let cancellation = CancellationToken::new();
+        let cancel = cancellation.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancel.cancel();
         });
         
-        state.pending_calls.push(PendingCall {
-            id: call_id.clone(),
-            assignment_var: call.assignment_var.clone(),
-            handle,
-        });
+        let input = await_approval(std::future::pending(), &cancellation).await.unwrap();
+        assert_eq!(input, None);
+    }
+
+    #[tokio::test]
+    async fn approval_returns_the_answer() {
+        let read = async { Ok(Ok("y\n".to_string())) };
+        let input = await_approval(read, &CancellationToken::new()).await.unwrap();
+        assert_eq!(input.as_deref(), Some("y\n"));
+    }
+
+    const MIXED: &str = "a = \"q\"; y = try { r = broken(a); return r } else { e => return e }; b = a; x = broken(b); t = (x, y); return t";
+    const PROTECTED: &str = "a = \"q\"; x = try { r = broken(a); return r } else { e => return e }; y = try { r = broken(a); return r } else { e => return e }; t = (x, y); return t";
+
+    async fn execute(source: &str, broken: &TestFunction, failure_mode: FailureMode) -> Result<ExecutionState> {
+        let options = ExecutionOptions::new(false).with_quiet(true).with_failure_mode(failure_mode);
+        let program = parse_program(source).unwrap();
+        execute_with_inputs(program, Arc::new(registry(&[broken])), options, HashMap::new()).await
+    }
+
+    fn failed_vars(state: &ExecutionState) -> HashSet<String> {
+        state.failures.iter().map(|failure| failure.assignment_var.clone()).collect()
+    }
+
+    fn slow_failure() -> TestFunction {
+        TestFunction::failing("broken", ValueType::Any).with_delay(Duration::from_millis(50))
+    }
+
+    #[tokio::test]
+    async fn shared_failure_aborts_when_any_variable_is_unprotected() {
+        let broken = slow_failure();
+        let result = execute(MIXED, &broken, FailureMode::Abort).await;
         
-        for stmt in &mut state.program.statements {
-            if stmt.variable == call.assignment_var {
-                stmt.expression = Expression::PendingCall(call_id.clone());
-                break;
-            }
-        }
+        assert!(matches!(result, Err(QuasarError::ExternalFunctionError(_))));
+        assert_eq!(broken.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn shared_failure_is_caught_when_every_variable_is_protected() {
+        let broken = slow_failure();
+        let state = execute(PROTECTED, &broken, FailureMode::Abort).await.unwrap();
+        
+        assert_eq!(state.status, ExecutionStatus::Completed);
+        assert_eq!(broken.calls(), 1);
+        assert_eq!(failed_vars(&state), HashSet::from([reserved_name("x", "try"), reserved_name("y", "try")]));
+    }
+
+    #[tokio::test]
+    async fn shared_failure_is_recorded_once_per_variable() {
+        let broken = slow_failure();
+        let state = execute(MIXED, &broken, FailureMode::BindError).await.unwrap();
+        
+        assert_eq!(state.status, ExecutionStatus::Completed);
+        assert_eq!(broken.calls(), 1);
+        assert_eq!(failed_vars(&state), HashSet::from(["x".to_string(), reserved_name("y", "try")]));
     }
-    
-    Ok(())
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
 use crate::ast::*;
-use crate::runtime::{ExecutionState, Result};
+use crate::runtime::{ExecutionState, Result, Rule};
 
 pub fn evaluate_join(state: &mut ExecutionState) -> Result<bool> {
     let mut changed = false;
@@ -7,82 +7,104 @@ pub fn evaluate_join(state: &mut ExecutionState) -> Result<bool> {
     
     let statements = state.program.statements.clone();
     for stmt in &statements {
-        match &stmt.expression {
-            Expression::Join(vars) => {
-                let mut all_resolved = true;
-                let mut joined_value = ConformValue::certain(Value::Primitive(PrimitiveValue::Null));
-                let mut first = true;
-                
-                for var in vars {
-                    if let Some(value) = state.lookup_var(var) {
-                        if first {
-                            joined_value = value.clone();
-                            first = false;
-                        } else {
-                            joined_value = joined_value.union(value);
-                        }
+        if evaluate_statement(state, stmt)?.is_some() {
+            changed = true;
+        } else {
+            new_statements.push(stmt.clone());
+        }
+    }
+    
+    if changed {
+        state.program.statements = new_statements;
+    }
+    
+    Ok(changed)
+}
+
+pub fn evaluate_statement(state: &mut ExecutionState, stmt: &Statement) -> Result<Option<Rule>> {
+    state.begin_reduction();
+    let rule = bind_statement(state, stmt)?;
+    if let Some(rule) = rule {
+        state.record_reduction(rule, stmt, &[])?;
+    }
+    Ok(rule)
+}
+
+fn bind_statement(state: &mut ExecutionState, stmt: &Statement) -> Result<Option<Rule>> {
+    match &stmt.expression {
+        Expression::Join(vars) => {
+            let mut all_resolved = true;
+            let mut joined_value = ConformValue::certain(Value::Primitive(PrimitiveValue::Null));
+            let mut first = true;
+            
+            for var in vars {
+                if let Some(value) = state.lookup_var(var) {
+                    if first {
+                        joined_value = value.clone();
+                        first = false;
                     } else {
-                        all_resolved = false;
-                        break;
+                        joined_value = joined_value.union(value);
                     }
-                }
-                
-                if all_resolved && !first {
-                    state.set_var(stmt.variable.clone(), joined_value);
-                    changed = true;
                 } else {
-                    new_statements.push(stmt.clone());
+                    all_resolved = false;
+                    break;
                 }
             }
             
-            Expression::Primitive(value) => {
-                state.set_var(stmt.variable.clone(), ConformValue::certain(value.clone()));
-                changed = true;
-            }
-            
-            Expression::AbstractPrimitive(cvalue) => {
-                state.set_var(stmt.variable.clone(), cvalue.clone());
-                changed = true;
+            if all_resolved && !first {
+                state.set_var(stmt.variable.clone(), joined_value);
+                Ok(Some(Rule::Join))
+            } else {
+                Ok(None)
             }
+        }
+        
+        Expression::Primitive(value) => {
+            state.set_var(stmt.variable.clone(), ConformValue::certain(value.clone()));
+            Ok(Some(Rule::Prim))
+        }
+        
+        Expression::AbstractPrimitive(cvalue) => {
+            state.set_var(stmt.variable.clone(), cvalue.clone());
+            Ok(Some(Rule::Prim))
+        }
+        
+        Expression::Tuple(vars) => {
+            let mut all_resolved = true;
+            let mut elements = Vec::new();
+            let mut error = None;
             
-            Expression::Tuple(vars) => {
-                let mut all_resolved = true;
-                let mut elements = Vec::new();
-                
-                for var in vars {
-                    if let Some(value) = state.lookup_var(var) {
-                        if let Some(certain_val) = value.as_certain() {
-                            elements.push(certain_val.clone());
-                        } else {
-                            all_resolved = false;
-                            break;
-                        }
+            for var in vars {
+                if let Some(value) = state.lookup_var(var) {
+                    if let Some(err) = value.failure() {
+                        error = Some(err.clone());
+                        break;
+                    } else if let Some(certain_val) = value.as_certain() {
+                        elements.push(certain_val.clone());
                     } else {
                         all_resolved = false;
                         break;
                     }
-                }
-                
-                if all_resolved {
-                    state.set_var(
-                        stmt.variable.clone(), 
-                        ConformValue::certain(Value::Tuple(elements))
-                    );
-                    changed = true;
                 } else {
-                    new_statements.push(stmt.clone());
+                    all_resolved = false;
+                    break;
                 }
             }
             
-            _ => {
-                new_statements.push(stmt.clone());
+            if let Some(error) = error {
+                state.set_var(stmt.variable.clone(), ConformValue::certain(error));
+                Ok(Some(Rule::Err))
+            } else if all_resolved {
+                state.set_var(
+                    stmt.variable.clone(), 
+                    ConformValue::certain(Value::Tuple(elements))
+                );
+                Ok(Some(Rule::Tuple))
+            } else {
+                Ok(None)
             }
         }
+        
+        _ => Ok(None),
     }
-    
-    if changed {
-        state.program.statements = new_statements;
-    }
-    
-    Ok(changed)
 }
\ No newline at end of file
@@ -1,54 +1,275 @@
-use crate::ast::Program;
-use crate::runtime::{ExecutionState, Result};
-use crate::interpreter::{apply_internal_rules, evaluate_join, find_dispatchable_calls, dispatch_calls, check_pending_calls};
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::ast::{Expression, Program, Value, ValueType};
+use crate::runtime::{Checkpoint, ExecutionState, ExecutionStatus, ExecutionOptions, FunctionRegistry, Result};
+use crate::interpreter::{apply_internal_rules, evaluate_join, find_dispatchable_calls, dispatch_calls, find_speculative_calls, dispatch_speculative_calls, spawn_call, check_pending_calls, check_program, infer_types, DispatchableCall};
 use tokio::time::{sleep, Duration};
 
 pub async fn execute(program: Program, with_approval: bool) -> Result<ExecutionState> {
-    let mut state = ExecutionState::new(program);
+    execute_with_registry(program, Arc::new(FunctionRegistry::with_builtins()), with_approval).await
+}
+
+pub async fn execute_with_registry(
+    program: Program,
+    registry: Arc<FunctionRegistry>,
+    with_approval: bool,
+) -> Result<ExecutionState> {
+    execute_with_options(program, registry, ExecutionOptions::new(with_approval)).await
+}
+
+pub async fn execute_with_options(
+    program: Program,
+    registry: Arc<FunctionRegistry>,
+    options: ExecutionOptions,
+) -> Result<ExecutionState> {
+    execute_with_inputs(program, registry, options, HashMap::new()).await
+}
+
+pub async fn execute_with_inputs(
+    program: Program,
+    registry: Arc<FunctionRegistry>,
+    options: ExecutionOptions,
+    inputs: HashMap<String, Value>,
+) -> Result<ExecutionState> {
+    check_program(&program, &registry)?;
+    
+    let mut state = ExecutionState::with_registry(program, registry).with_options(options);
+    state.bind_inputs(inputs)?;
+    run(&mut state).await?;
+    
+    Ok(state)
+}
+
+pub async fn resume(
+    checkpoint: Checkpoint,
+    registry: Arc<FunctionRegistry>,
+    options: ExecutionOptions,
+) -> Result<ExecutionState> {
+    let env = checkpoint.scope.iter().map(|(name, value)| {
+        let value_type = match ValueType::of_conform(value) {
+            ValueType::Error => ValueType::Any,
+            value_type => value_type,
+        };
+        (name.clone(), value_type)
+    }).collect();
+    infer_types(&checkpoint.program, &registry, env)?;
+    
+    let pending = checkpoint.pending_calls.clone();
+    let mut state = ExecutionState::from_checkpoint(checkpoint, registry).with_options(options);
     
-    println!("=== Starting QUASAR Execution ===\n");
+    for descriptor in pending {
+        let idempotent = state.registry.signature(&descriptor.function)
+            .is_some_and(|signature| signature.effects.idempotent);
+        
+        match state.registry.get(&descriptor.function) {
+            Some(func) if idempotent => {
+                state.log(&format!("Re-executing {} call {} for {}", descriptor.function, descriptor.id, descriptor.assignment_var));
+                let call = DispatchableCall {
+                    assignment_var: descriptor.assignment_var,
+                    function: descriptor.function,
+                    argument: descriptor.argument,
+                };
+                spawn_call(&mut state, descriptor.id, &call, func);
+            }
+            _ => {
+                state.log(&format!("Re-dispatching {} call {} for {}", descriptor.function, descriptor.id, descriptor.assignment_var));
+                let argument_var = format!("{}_arg", descriptor.id);
+                state.set_var(argument_var.clone(), descriptor.argument);
+                for stmt in &mut state.program.statements {
+                    if stmt.expression == Expression::PendingCall(descriptor.id.clone()) {
+                        stmt.expression = Expression::ExternalCall {
+                            function: descriptor.function.clone(),
+                            argument: argument_var.clone(),
+                        };
+                    }
+                }
+            }
+        }
+    }
+    
+    run(&mut state).await?;
+    
+    Ok(state)
+}
+
+pub async fn run(state: &mut ExecutionState) -> Result<()> {
+    drive(state, false).await
+}
+
+pub async fn run_until_return(state: &mut ExecutionState) -> Result<()> {
+    drive(state, true).await
+}
+
+async fn drive(state: &mut ExecutionState, until_return: bool) -> Result<()> {
+    let with_approval = state.options.with_approval;
+    let cancellation = state.options.cancellation.clone();
+    state.status = ExecutionStatus::Running;
+    
+    state.log("=== Starting QUASAR Execution ===\n");
     
     loop {
-        let dispatchable = find_dispatchable_calls(&state);
+        if cancellation.is_cancelled() {
+            break;
+        }
+        
+        let mut progressed = false;
+        let dispatchable = find_dispatchable_calls(state);
         
         if !dispatchable.is_empty() {
-            println!("Found {} dispatchable calls", dispatchable.len());
-            dispatch_calls(&mut state, dispatchable, with_approval).await?;
+            state.log(&format!("Found {} dispatchable calls", dispatchable.len()));
+            dispatch_calls(state, dispatchable, with_approval).await?;
+            progressed = true;
+        }
+        
+        let speculative = find_speculative_calls(state);
+        if !speculative.is_empty() {
+            progressed |= dispatch_speculative_calls(state, speculative)?;
         }
         
         loop {
             let mut changed = false;
             
-            changed |= check_pending_calls(&mut state).await?;
+            changed |= check_pending_calls(state).await?;
             
-            changed |= apply_internal_rules(&mut state)?;
+            changed |= apply_internal_rules(state)?;
             
-            changed |= evaluate_join(&mut state)?;
+            changed |= evaluate_join(state)?;
             
             if !changed {
                 break;
             }
+            progressed = true;
+        }
+        
+        if progressed {
+            if let Some(path) = &state.options.checkpoint_path {
+                state.checkpoint().save(path)?;
+            }
+        }
+        
+        if until_return && state.lookup_var(&state.program.return_var).is_some() {
+            break;
         }
         
-        if state.pending_calls.is_empty() && find_dispatchable_calls(&state).is_empty() {
+        if state.pending_calls.iter().all(|pc| state.is_speculative(&pc.id)) && find_dispatchable_calls(state).is_empty() {
             break;
         }
         
         if !state.pending_calls.is_empty() {
-            println!("Waiting for {} pending calls...", state.pending_calls.len());
-            sleep(Duration::from_millis(100)).await;
+            let queued = state.registry.scheduler().queued();
+            if queued > 0 {
+                state.log(&format!("Waiting for {} pending calls ({} queued)...", state.pending_calls.len(), queued));
+            } else {
+                state.log(&format!("Waiting for {} pending calls...", state.pending_calls.len()));
+            }
+            tokio::select! {
+                _ = sleep(Duration::from_millis(100)) => {}
+                _ = cancellation.cancelled() => {}
+            }
+        }
+    }
+    
+    if !until_return {
+        state.discard_all_speculation();
+    }
+    
+    if cancellation.is_cancelled() {
+        state.abort_pending_calls();
+        state.status = ExecutionStatus::Cancelled;
+        state.log("\n=== Execution Cancelled ===");
+    } else if state.lookup_var(&state.program.return_var).is_some() {
+        state.status = ExecutionStatus::Completed;
+        state.log("\n=== Execution Complete ===");
+    } else {
+        state.status = ExecutionStatus::Stuck;
+        state.log("\n=== Execution Stuck ===");
+    }
+    
+    if !state.options.quiet {
+        print_summary(state);
+    }
+    
+    if let Some(replay) = state.registry.replay() {
+        if !until_return && state.status != ExecutionStatus::Cancelled {
+            replay.verify()?;
         }
     }
     
-    println!("\n=== Execution Complete ===");
+    Ok(())
+}
+
+fn print_summary(state: &ExecutionState) {
     println!("Final scope:");
     for (var, value) in &state.scope {
         println!("  {} = {:?}", var, value);
     }
     
+    if !state.failures.is_empty() {
+        println!("\nFailed calls:");
+        for failure in &state.failures {
+            println!("  {} = {}({:?}): {}", failure.assignment_var, failure.function, failure.argument, failure.error);
+        }
+    }
+    
+    if !state.rejected.is_empty() {
+        println!("\nRejected calls:");
+        for rejected in &state.rejected {
+            println!("  {} = {}({:?})", rejected.assignment_var, rejected.function, rejected.argument);
+        }
+    }
+    
+    if state.speculation.dispatched > 0 {
+        let stats = state.speculation;
+        println!("\nSpeculative calls: {} dispatched, {} used, {} wasted", stats.dispatched, stats.used, stats.wasted);
+    }
+    
     if let Some(return_value) = state.lookup_var(&state.program.return_var) {
         println!("\nReturn value: {:?}", return_value);
     }
-    
-    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::runtime::CancellationToken;
+    use crate::syntax::parse_program;
+
+    #[tokio::test]
+    async fn cancellation_returns_partial_state() {
+        let slow = TestFunction::echo("slow").with_delay(Duration::from_secs(10));
+        let cancellation = CancellationToken::new();
+        let cancel = cancellation.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        
+        let start = Instant::now();
+        let program = parse_program("a = \"q\"; b = a; x = slow(a); return x").unwrap();
+        let options = ExecutionOptions::new(false).with_quiet(true).with_cancellation(cancellation);
+        let state = execute_with_inputs(program, Arc::new(registry(&[&slow])), options, HashMap::new()).await.unwrap();
+        
+        assert_eq!(state.status, ExecutionStatus::Cancelled);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(state.lookup_var("b").is_some());
+        assert!(state.lookup_var("x").is_none());
+        sleep(Duration::from_millis(10)).await;
+        assert!(state.pending_calls.iter().all(|pc| pc.handle.is_finished()));
+    }
+
+    #[tokio::test]
+    async fn cancelled_token_stops_dispatching() {
+        let echo = TestFunction::echo("echo");
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        
+        let program = parse_program("a = \"q\"; x = echo(a); return x").unwrap();
+        let options = ExecutionOptions::new(false).with_quiet(true).with_cancellation(cancellation);
+        let state = execute_with_inputs(program, Arc::new(registry(&[&echo])), options, HashMap::new()).await.unwrap();
+        
+        assert_eq!(state.status, ExecutionStatus::Cancelled);
+        assert_eq!(echo.calls(), 0);
+    }
 }
\ No newline at end of file
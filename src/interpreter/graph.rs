@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use serde::{Deserialize, Serialize};
+use crate::ast::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum NodeKind {
+    Input,
+    Parameter,
+    Value,
+    Call { function: String },
+    Pending,
+    Fold,
+    If,
+    Try,
+    Join,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeKind {
+    Data,
+    Control,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: usize,
+    pub variable: String,
+    pub scope: Vec<String>,
+    #[serde(flatten)]
+    pub kind: NodeKind,
+}
+
+impl GraphNode {
+    pub fn function(&self) -> Option<&str> {
+        match &self.kind {
+            NodeKind::Call { function } => Some(function),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match &self.kind {
+            NodeKind::Input => format!("input {}", self.variable),
+            NodeKind::Parameter | NodeKind::Value => self.variable.clone(),
+            NodeKind::Call { function } => format!("{} = {}()", self.variable, function),
+            NodeKind::Pending => format!("{} = pending", self.variable),
+            NodeKind::Fold => format!("{} = fold", self.variable),
+            NodeKind::If => format!("{} = if", self.variable),
+            NodeKind::Try => format!("{} = try", self.variable),
+            NodeKind::Join => format!("{} = join", self.variable),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub return_node: Option<usize>,
+}
+
+impl DependencyGraph {
+    pub fn build(program: &Program) -> Self {
+        let mut graph = DependencyGraph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            return_node: None,
+        };
+        let mut env = HashMap::new();
+        for input in &program.inputs {
+            let id = graph.add_node(&input.name, &[], NodeKind::Input);
+            env.insert(input.name.clone(), id);
+        }
+        graph.add_statements(&program.statements, &[], None, &mut env);
+        graph.return_node = env.get(&program.return_var).copied();
+        graph
+    }
+
+    pub fn dependencies(&self, id: usize) -> impl Iterator<Item = &GraphEdge> {
+        self.edges.iter().filter(move |edge| edge.to == id)
+    }
+
+    pub fn dependents(&self, id: usize) -> impl Iterator<Item = &GraphEdge> {
+        self.edges.iter().filter(move |edge| edge.from == id)
+    }
+
+    pub fn calls(&self) -> impl Iterator<Item = &GraphNode> {
+        self.nodes.iter().filter(|node| node.function().is_some())
+    }
+
+    pub fn critical_path(&self) -> Vec<usize> {
+        let (path, calls) = self.longest_path(|node| if node.function().is_some() { 1.0 } else { 0.0 });
+        if calls > 0.0 { path } else { Vec::new() }
+    }
+
+    pub fn longest_path(&self, weight: impl Fn(&GraphNode) -> f64) -> (Vec<usize>, f64) {
+        let mut distance = vec![0.0_f64; self.nodes.len()];
+        let mut previous = vec![None; self.nodes.len()];
+        for node in &self.nodes {
+            let best = self.dependencies(node.id)
+                .max_by(|a, b| distance[a.from].total_cmp(&distance[b.from]));
+            if let Some(edge) = best {
+                distance[node.id] = distance[edge.from];
+                previous[node.id] = Some(edge.from);
+            }
+            distance[node.id] += weight(node);
+        }
+        
+        let Some(end) = (0..self.nodes.len()).max_by(|a, b| distance[*a].total_cmp(&distance[*b])) else {
+            return (Vec::new(), 0.0);
+        };
+        let mut path = vec![end];
+        while let Some(id) = previous[*path.last().unwrap()] {
+            path.push(id);
+        }
+        path.reverse();
+        (path, distance[end])
+    }
+
+    pub fn to_dot(&self) -> String {
+        let critical = self.critical_path();
+        let on_path = |from: usize, to: usize| critical.windows(2).any(|pair| pair == [from, to]);
+        
+        let mut out = String::from("digraph program {\n    rankdir=TB;\n    node [fontname=\"monospace\"];\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Call { .. } | NodeKind::Pending => "box",
+                NodeKind::Input => "invhouse",
+                NodeKind::Parameter => "plaintext",
+                _ => "ellipse",
+            };
+            let mut attributes = format!("label=\"{}\", shape={}", escape(&node.label()), shape);
+            if critical.contains(&node.id) {
+                attributes.push_str(", color=red, penwidth=2");
+            }
+            if !node.scope.is_empty() {
+                write!(attributes, ", tooltip=\"{}\"", escape(&node.scope.join(" / "))).unwrap();
+            }
+            writeln!(out, "    n{} [{}];", node.id, attributes).unwrap();
+        }
+        for edge in &self.edges {
+            let mut attributes = Vec::new();
+            if edge.kind == EdgeKind::Control {
+                attributes.push("style=dashed");
+            }
+            if on_path(edge.from, edge.to) {
+                attributes.push("color=red, penwidth=2");
+            }
+            if attributes.is_empty() {
+                writeln!(out, "    n{} -> n{};", edge.from, edge.to).unwrap();
+            } else {
+                writeln!(out, "    n{} -> n{} [{}];", edge.from, edge.to, attributes.join(", ")).unwrap();
+            }
+        }
+        out.push('}');
+        out
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let critical = self.critical_path();
+        
+        let mut out = String::from("flowchart TD\n");
+        for node in &self.nodes {
+            let label = node.label().replace('"', "#quot;");
+            let shape = match node.kind {
+                NodeKind::Call { .. } | NodeKind::Pending => format!("[\"{}\"]", label),
+                NodeKind::Input => format!("[/\"{}\"/]", label),
+                _ => format!("([\"{}\"])", label),
+            };
+            writeln!(out, "    n{}{}", node.id, shape).unwrap();
+        }
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::Data => "-->",
+                EdgeKind::Control => "-.->",
+            };
+            writeln!(out, "    n{} {} n{}", edge.from, arrow, edge.to).unwrap();
+        }
+        if !critical.is_empty() {
+            let ids: Vec<String> = critical.iter().map(|id| format!("n{}", id)).collect();
+            out.push_str("    classDef critical stroke:#d00,stroke-width:2px\n");
+            write!(out, "    class {} critical", ids.join(",")).unwrap();
+        }
+        out.trim_end().to_string()
+    }
+
+    fn add_node(&mut self, variable: &str, scope: &[String], kind: NodeKind) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(GraphNode {
+            id,
+            variable: variable.to_string(),
+            scope: scope.to_vec(),
+            kind,
+        });
+        id
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, kind: EdgeKind) {
+        if !self.edges.iter().any(|edge| edge.from == from && edge.to == to) {
+            self.edges.push(GraphEdge { from, to, kind });
+        }
+    }
+
+    fn add_statements(
+        &mut self,
+        statements: &[Statement],
+        scope: &[String],
+        entry: Option<usize>,
+        env: &mut HashMap<String, usize>,
+    ) {
+        for stmt in statements {
+            let mut inner = scope.to_vec();
+            inner.push(stmt.variable.clone());
+            
+            let mut results = Vec::new();
+            for (block, sources, edge_kind) in blocks(&stmt.expression) {
+                let parameter = self.add_node(&block.parameter, &inner, NodeKind::Parameter);
+                for source in sources.iter().filter_map(|var| env.get(*var)) {
+                    self.add_edge(*source, parameter, edge_kind.clone());
+                }
+                let mut block_env = env.clone();
+                block_env.insert(block.parameter.clone(), parameter);
+                self.add_statements(&block.body, &inner, Some(parameter), &mut block_env);
+                let result = block_env.get(&block.return_var).copied();
+                results.extend(result.map(|result| (result, EdgeKind::Data)));
+                if result.is_none_or(|result| result < parameter) {
+                    results.push((parameter, EdgeKind::Control));
+                }
+            }
+            
+            let id = self.add_node(&stmt.variable, scope, node_kind(&stmt.expression));
+            for var in uses(&stmt.expression) {
+                if let Some(source) = env.get(var) {
+                    self.add_edge(*source, id, EdgeKind::Data);
+                }
+            }
+            for (result, edge_kind) in results {
+                self.add_edge(result, id, edge_kind);
+            }
+            if let Some(entry) = entry {
+                if !self.dependencies(id).any(|edge| edge.from >= entry) {
+                    self.add_edge(entry, id, EdgeKind::Control);
+                }
+            }
+            env.insert(stmt.variable.clone(), id);
+        }
+    }
+}
+
+fn node_kind(expression: &Expression) -> NodeKind {
+    match expression {
+        Expression::ExternalCall { function, .. } => NodeKind::Call { function: function.clone() },
+        Expression::PendingCall(_) => NodeKind::Pending,
+        Expression::Fold { .. } => NodeKind::Fold,
+        Expression::If { .. } => NodeKind::If,
+        Expression::Try { .. } | Expression::Catch { .. } => NodeKind::Try,
+        Expression::Join(_) => NodeKind::Join,
+        _ => NodeKind::Value,
+    }
+}
+
+fn uses(expression: &Expression) -> Vec<&String> {
+    match expression {
+        Expression::Variable(var) => vec![var],
+        Expression::Tuple(vars) | Expression::Join(vars) => vars.iter().collect(),
+        Expression::ExternalCall { argument, .. } => vec![argument],
+        Expression::Projection { variable, .. } => vec![variable],
+        _ => Vec::new(),
+    }
+}
+
+fn blocks(expression: &Expression) -> Vec<(&Block, Vec<&String>, EdgeKind)> {
+    match expression {
+        Expression::Fold { list, initial, block } => vec![(block, vec![list, initial], EdgeKind::Data)],
+        Expression::If { condition, then_block, else_block } => std::iter::once(then_block)
+            .chain(else_block)
+            .map(|block| (block, vec![condition], EdgeKind::Control))
+            .collect(),
+        Expression::Try { body, handler } => vec![(body, Vec::new(), EdgeKind::Control), (handler, Vec::new(), EdgeKind::Control)],
+        Expression::Catch { value, handler } => vec![(handler, vec![value], EdgeKind::Data)],
+        _ => Vec::new(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::parse_program;
+
+    fn graph(source: &str) -> DependencyGraph {
+        DependencyGraph::build(&parse_program(source).unwrap())
+    }
+
+    fn edge(from: usize, to: usize, kind: EdgeKind) -> GraphEdge {
+        GraphEdge { from, to, kind }
+    }
+
+    #[test]
+    fn statements_depend_on_the_variables_they_use() {
+        let graph = graph("input q: String; a = find(q); b = exists(q); c = (a, b); return c");
+        
+        let kinds: Vec<_> = graph.nodes.iter().map(|node| node.kind.clone()).collect();
+        assert_eq!(kinds, [
+            NodeKind::Input,
+            NodeKind::Call { function: "find".to_string() },
+            NodeKind::Call { function: "exists".to_string() },
+            NodeKind::Value,
+        ]);
+        assert_eq!(graph.edges, [
+            edge(0, 1, EdgeKind::Data),
+            edge(0, 2, EdgeKind::Data),
+            edge(1, 3, EdgeKind::Data),
+            edge(2, 3, EdgeKind::Data),
+        ]);
+        assert_eq!(graph.return_node, Some(3));
+    }
+
+    #[test]
+    fn block_statements_are_scoped_and_gated() {
+        let graph = graph("c = true; x = if c { a = find(c); return a } else { return c }; return x");
+        let node = |variable: &str| graph.nodes.iter().find(|node| node.variable == variable).unwrap();
+        let (c, a, x) = (node("c").id, node("a").id, node("x").id);
+        let then_parameter = a - 1;
+        
+        assert_eq!(node("a").scope, ["x"]);
+        assert_eq!(node("x").kind, NodeKind::If);
+        assert!(graph.edges.contains(&edge(c, then_parameter, EdgeKind::Control)));
+        assert!(graph.edges.contains(&edge(then_parameter, a, EdgeKind::Control)));
+        assert!(graph.edges.contains(&edge(a, x, EdgeKind::Data)));
+    }
+
+    #[test]
+    fn critical_path_follows_the_longest_chain_of_calls() {
+        let chained = graph("input q: String; a = find(q); b = exists(a); c = exists(q); d = (b, c); return d");
+        assert_eq!(chained.critical_path(), [0, 1, 2, 4]);
+        
+        assert!(graph("a = 1; b = (a, a); return b").critical_path().is_empty());
+    }
+
+    #[test]
+    fn exports_highlight_the_critical_path() {
+        let graph = graph("input q: String; a = find(q); c = true; x = if c { return a } else { return q }; return x");
+        
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph program {"));
+        assert!(dot.contains("n1 [label=\"a = find()\", shape=box, color=red, penwidth=2];"));
+        assert!(dot.contains("n0 -> n1 [color=red, penwidth=2];"));
+        assert!(dot.contains("style=dashed"));
+        
+        let mermaid = graph.to_mermaid();
+        let critical: Vec<String> = graph.critical_path().iter().map(|id| format!("n{}", id)).collect();
+        assert!(mermaid.starts_with("flowchart TD\n    n0[/\"input q\"/]"));
+        assert!(mermaid.contains("-.->"));
+        assert!(mermaid.ends_with(&format!("class {} critical", critical.join(","))));
+    }
+}
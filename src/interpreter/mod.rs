@@ -2,8 +2,20 @@ pub mod rewriter;
 pub mod dispatcher;
 pub mod evaluator;
 pub mod executor;
+pub mod checker;
+pub mod graph;
+pub mod parallelism;
+pub mod stepper;
+pub mod session;
+pub mod debugger;
 
 pub use rewriter::*;
 pub use dispatcher::*;
 pub use evaluator::*;
-pub use executor::*;
\ No newline at end of file
+pub use executor::*;
+pub use checker::*;
+pub use graph::*;
+pub use parallelism::*;
+pub use stepper::*;
+pub use session::*;
+pub use debugger::*;
\ No newline at end of file
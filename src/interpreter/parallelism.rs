@@ -0,0 +1,393 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Duration;
+use serde::{Serialize, Serializer};
+use crate::ast::*;
+use crate::runtime::FunctionRegistry;
+
+pub const DEFAULT_LIST_LENGTH: usize = 10;
+pub const DEFAULT_LATENCY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallCount {
+    terms: BTreeMap<Vec<String>, u64>,
+}
+
+impl CallCount {
+    fn term(lengths: &[String], coefficient: u64) -> Self {
+        let mut count = CallCount::default();
+        if coefficient > 0 {
+            count.terms.insert(lengths.to_vec(), coefficient);
+        }
+        count
+    }
+
+    pub fn evaluate(&self, lengths: &BTreeMap<String, usize>) -> u64 {
+        self.terms.iter().map(|(symbols, coefficient)| {
+            symbols.iter().fold(*coefficient, |product, symbol| {
+                product * lengths.get(symbol).copied().unwrap_or(DEFAULT_LIST_LENGTH) as u64
+            })
+        }).sum()
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &String> {
+        self.terms.keys().flatten()
+    }
+
+    fn add(&mut self, other: &CallCount) {
+        for (symbols, coefficient) in &other.terms {
+            *self.terms.entry(symbols.clone()).or_insert(0) += coefficient;
+        }
+    }
+
+    fn max(&mut self, other: &CallCount) {
+        for (symbols, coefficient) in &other.terms {
+            let entry = self.terms.entry(symbols.clone()).or_insert(0);
+            *entry = (*entry).max(*coefficient);
+        }
+    }
+}
+
+impl fmt::Display for CallCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|(symbols, _)| symbols.len());
+        let terms: Vec<String> = terms.into_iter().map(|(symbols, coefficient)| {
+            let mut factors: Vec<String> = symbols.iter().map(|symbol| format!("|{}|", symbol)).collect();
+            if *coefficient != 1 || factors.is_empty() {
+                factors.insert(0, coefficient.to_string());
+            }
+            factors.join("·")
+        }).collect();
+        write!(f, "{}", terms.join(" + "))
+    }
+}
+
+impl Serialize for CallCount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionEstimate {
+    pub function: String,
+    pub calls: CallCount,
+    pub conditional: bool,
+    pub expected_calls: u64,
+    pub latency_ms: u64,
+    pub latency_known: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParallelismReport {
+    pub functions: Vec<FunctionEstimate>,
+    pub lengths: BTreeMap<String, usize>,
+    pub max_concurrency: usize,
+    pub widest_layer: BTreeMap<String, usize>,
+    pub critical_path_ms: u64,
+    pub sequential_ms: u64,
+}
+
+impl ParallelismReport {
+    pub fn analyze(program: &Program, registry: &FunctionRegistry, lengths: &HashMap<String, usize>) -> Self {
+        let counts = count_calls(&program.statements, &[], 1, false, &mut HashMap::new());
+        
+        let mut timeline = Timeline {
+            registry,
+            lengths: lengths.iter().map(|(name, length)| (name.clone(), *length)).collect(),
+            used: BTreeMap::new(),
+            calls: Vec::new(),
+        };
+        for symbol in counts.values().flat_map(|tally| tally.calls.symbols()) {
+            timeline.length(&Length::Symbol(symbol.clone()));
+        }
+        let mut ready = HashMap::new();
+        timeline.schedule(&program.statements, 0.0, &mut ready, &mut HashMap::new());
+        let finish = ready.get(&program.return_var).copied().unwrap_or(0.0);
+        
+        let functions = counts.into_iter().map(|(function, tally)| {
+            let latency = registry.latency_estimate(&function);
+            FunctionEstimate {
+                expected_calls: tally.calls.evaluate(&timeline.used),
+                function,
+                calls: tally.calls,
+                conditional: tally.conditional,
+                latency_ms: latency.unwrap_or(DEFAULT_LATENCY).as_millis() as u64,
+                latency_known: latency.is_some(),
+            }
+        }).collect();
+        let (max_concurrency, widest_layer) = timeline.widest_layer();
+        
+        ParallelismReport {
+            functions,
+            lengths: timeline.used,
+            max_concurrency,
+            widest_layer,
+            critical_path_ms: finish.max(timeline.calls.iter().map(|call| call.end).fold(0.0, f64::max)) as u64,
+            sequential_ms: timeline.calls.iter().map(|call| call.end - call.start).sum::<f64>() as u64,
+        }
+    }
+
+    pub fn total_calls(&self) -> u64 {
+        self.functions.iter().map(|estimate| estimate.expected_calls).sum()
+    }
+
+    pub fn speedup(&self) -> f64 {
+        if self.critical_path_ms == 0 {
+            1.0
+        } else {
+            self.sequential_ms as f64 / self.critical_path_ms as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Length {
+    Known(usize),
+    Symbol(String),
+}
+
+fn length_of(lengths: &HashMap<String, Length>, var: &str) -> Length {
+    lengths.get(var).cloned().unwrap_or_else(|| Length::Symbol(var.to_string()))
+}
+
+fn record_length(lengths: &mut HashMap<String, Length>, stmt: &Statement) {
+    let length = match &stmt.expression {
+        Expression::Primitive(Value::List(items)) => Length::Known(items.len()),
+        Expression::Variable(var) => length_of(lengths, var),
+        _ => return,
+    };
+    lengths.insert(stmt.variable.clone(), length);
+}
+
+struct Tally {
+    calls: CallCount,
+    conditional: bool,
+}
+
+fn count_calls(
+    statements: &[Statement],
+    symbols: &[String],
+    coefficient: u64,
+    conditional: bool,
+    lengths: &mut HashMap<String, Length>,
+) -> BTreeMap<String, Tally> {
+    let mut counts = BTreeMap::new();
+    for stmt in statements {
+        match &stmt.expression {
+            Expression::ExternalCall { function, .. } => {
+                let mut calls = BTreeMap::new();
+                calls.insert(function.clone(), Tally { calls: CallCount::term(symbols, coefficient), conditional });
+                add_counts(&mut counts, calls);
+            }
+            Expression::Fold { list, block, .. } => {
+                let mut symbols = symbols.to_vec();
+                let coefficient = match length_of(lengths, list) {
+                    Length::Known(length) => coefficient * length as u64,
+                    Length::Symbol(symbol) => {
+                        symbols.push(symbol);
+                        symbols.sort();
+                        coefficient
+                    }
+                };
+                add_counts(&mut counts, count_calls(&block.body, &symbols, coefficient, conditional, &mut lengths.clone()));
+            }
+            Expression::If { then_block, else_block, .. } => {
+                let mut branches = count_calls(&then_block.body, symbols, coefficient, true, &mut lengths.clone());
+                if let Some(else_block) = else_block {
+                    for (function, tally) in count_calls(&else_block.body, symbols, coefficient, true, &mut lengths.clone()) {
+                        match branches.get_mut(&function) {
+                            Some(existing) => existing.calls.max(&tally.calls),
+                            None => {
+                                branches.insert(function, tally);
+                            }
+                        }
+                    }
+                }
+                add_counts(&mut counts, branches);
+            }
+            Expression::Try { body, handler } => {
+                add_counts(&mut counts, count_calls(&body.body, symbols, coefficient, conditional, &mut lengths.clone()));
+                add_counts(&mut counts, count_calls(&handler.body, symbols, coefficient, true, &mut lengths.clone()));
+            }
+            Expression::Catch { handler, .. } => {
+                add_counts(&mut counts, count_calls(&handler.body, symbols, coefficient, true, &mut lengths.clone()));
+            }
+            _ => {}
+        }
+        record_length(lengths, stmt);
+    }
+    counts
+}
+
+fn add_counts(counts: &mut BTreeMap<String, Tally>, other: BTreeMap<String, Tally>) {
+    for (function, tally) in other {
+        match counts.get_mut(&function) {
+            Some(existing) => {
+                existing.calls.add(&tally.calls);
+                existing.conditional &= tally.conditional;
+            }
+            None => {
+                counts.insert(function, tally);
+            }
+        }
+    }
+}
+
+struct ScheduledCall {
+    function: String,
+    start: f64,
+    end: f64,
+}
+
+struct Timeline<'a> {
+    registry: &'a FunctionRegistry,
+    lengths: BTreeMap<String, usize>,
+    used: BTreeMap<String, usize>,
+    calls: Vec<ScheduledCall>,
+}
+
+impl Timeline<'_> {
+    fn length(&mut self, length: &Length) -> usize {
+        match length {
+            Length::Known(length) => *length,
+            Length::Symbol(symbol) => {
+                let length = self.lengths.get(symbol).copied().unwrap_or(DEFAULT_LIST_LENGTH);
+                self.used.insert(symbol.clone(), length);
+                length
+            }
+        }
+    }
+
+    fn schedule(
+        &mut self,
+        statements: &[Statement],
+        gate: f64,
+        ready: &mut HashMap<String, f64>,
+        lengths: &mut HashMap<String, Length>,
+    ) {
+        for stmt in statements {
+            let at = |vars: &[&String]| vars.iter().filter_map(|var| ready.get(*var)).copied().fold(gate, f64::max);
+            let finish = match &stmt.expression {
+                Expression::ExternalCall { function, argument } => {
+                    let start = at(&[argument]);
+                    let latency = self.registry.latency_estimate(function).unwrap_or(DEFAULT_LATENCY);
+                    let end = start + latency.as_secs_f64() * 1000.0;
+                    self.calls.push(ScheduledCall { function: function.clone(), start, end });
+                    end
+                }
+                Expression::Variable(var) | Expression::Projection { variable: var, .. } | Expression::Catch { value: var, .. } => at(&[var]),
+                Expression::Tuple(vars) | Expression::Join(vars) => at(&vars.iter().collect::<Vec<_>>()),
+                Expression::Fold { list, initial, block } => {
+                    let iterations = self.length(&length_of(lengths, list));
+                    let mut accumulator = at(&[list, initial]);
+                    for _ in 0..iterations {
+                        accumulator = self.run_block(block, accumulator, ready, lengths);
+                    }
+                    accumulator
+                }
+                Expression::If { condition, then_block, else_block } => {
+                    let condition = at(&[condition]);
+                    let before = self.calls.len();
+                    let then_finish = self.run_block(then_block, condition, ready, lengths);
+                    let then_calls = self.calls.split_off(before);
+                    let else_finish = match else_block {
+                        Some(block) => self.run_block(block, condition, ready, lengths),
+                        None => condition,
+                    };
+                    if then_finish >= else_finish {
+                        self.calls.truncate(before);
+                        self.calls.extend(then_calls);
+                    }
+                    then_finish.max(else_finish)
+                }
+                Expression::Try { body, .. } => self.run_block(body, gate, ready, lengths),
+                _ => gate,
+            };
+            ready.insert(stmt.variable.clone(), finish);
+            record_length(lengths, stmt);
+        }
+    }
+
+    fn run_block(&mut self, block: &Block, start: f64, ready: &HashMap<String, f64>, lengths: &HashMap<String, Length>) -> f64 {
+        let mut ready = ready.clone();
+        ready.insert(block.parameter.clone(), start);
+        self.schedule(&block.body, start, &mut ready, &mut lengths.clone());
+        ready.get(&block.return_var).copied().unwrap_or(start).max(start)
+    }
+
+    fn widest_layer(&self) -> (usize, BTreeMap<String, usize>) {
+        let mut widest = (0, BTreeMap::new());
+        for call in &self.calls {
+            let running: Vec<&ScheduledCall> = self.calls.iter()
+                .filter(|other| other.start <= call.start && call.start < other.end)
+                .collect();
+            if running.len() > widest.0 {
+                let mut layer = BTreeMap::new();
+                for other in &running {
+                    *layer.entry(other.function.clone()).or_insert(0) += 1;
+                }
+                widest = (running.len(), layer);
+            }
+        }
+        widest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::syntax::parse_program;
+
+    fn analyze(source: &str, lengths: &[(&str, usize)]) -> ParallelismReport {
+        let echo = TestFunction::echo("echo");
+        let lengths = lengths.iter().map(|(name, length)| (name.to_string(), *length)).collect();
+        ParallelismReport::analyze(&parse_program(source).unwrap(), &registry(&[&echo]), &lengths)
+    }
+
+    #[test]
+    fn fold_calls_scale_with_list_length() {
+        let symbolic = "input items; i = 0; r = fold items i { p => y = echo(p); return y }; return r";
+        let report = analyze(symbolic, &[]);
+        assert_eq!(report.functions[0].calls.to_string(), "|items|");
+        assert_eq!(report.functions[0].expected_calls, DEFAULT_LIST_LENGTH as u64);
+        assert_eq!(analyze(symbolic, &[("items", 3)]).total_calls(), 3);
+        
+        let known = analyze("l = [1, 2, 3]; i = 0; r = fold l i { p => y = echo(p); return y }; return r", &[]);
+        assert_eq!(known.functions[0].calls.to_string(), "3");
+        assert!(known.lengths.is_empty());
+        
+        let nested = "input l; input m; i = 0; r = fold l i { p => s = fold m p { q => y = echo(q); return y }; return s }; return r";
+        let report = analyze(nested, &[("l", 2), ("m", 3)]);
+        assert_eq!(report.functions[0].calls.to_string(), "|l|·|m|");
+        assert_eq!(report.total_calls(), 6);
+    }
+
+    #[test]
+    fn branches_count_the_larger_side() {
+        let report = analyze(
+            "c = true; a = \"q\"; x = if c { y = echo(a); z = echo(y); return z } else { w = echo(a); return w }; return x",
+            &[],
+        );
+        assert_eq!(report.functions[0].expected_calls, 2);
+        assert!(report.functions[0].conditional);
+        assert_eq!(report.critical_path_ms, 2 * DEFAULT_LATENCY.as_millis() as u64);
+    }
+
+    #[test]
+    fn independent_calls_overlap() {
+        let report = analyze("a = \"q\"; x = echo(a); y = echo(a); z = echo(x); t = (y, z); return t", &[]);
+        let latency = DEFAULT_LATENCY.as_millis() as u64;
+        
+        assert_eq!(report.max_concurrency, 2);
+        assert_eq!(report.widest_layer, BTreeMap::from([("echo".to_string(), 2)]));
+        assert_eq!(report.critical_path_ms, 2 * latency);
+        assert_eq!(report.sequential_ms, 3 * latency);
+        assert_eq!(report.speedup(), 1.5);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::ast::*;
-use crate::runtime::{ExecutionState, Result, QuasarError};
+use crate::runtime::{ExecutionState, Result, QuasarError, Rule, call_key};
 
 pub fn apply_internal_rules(state: &mut ExecutionState) -> Result<bool> {
     let mut changed = false;
@@ -7,138 +7,284 @@ pub fn apply_internal_rules(state: &mut ExecutionState) -> Result<bool> {
     
     let statements = state.program.statements.clone();
     for stmt in &statements {
-        match &stmt.expression {
-            Expression::Variable(src_var) => {
-                if let Some(value) = state.lookup_var(src_var) {
-                    state.set_var(stmt.variable.clone(), value.clone());
-                    changed = true;
-                } else {
-                    new_statements.push(stmt.clone());
-                }
+        match rewrite_statement(state, stmt)? {
+            Some((_, replacement)) => {
+                new_statements.extend(replacement);
+                changed = true;
             }
-            
-            Expression::Projection { index, variable } => {
-                if let Some(value) = state.lookup_var(variable) {
-                    if let Some(certain_val) = value.as_certain() {
-                        if let Value::Tuple(elements) = certain_val {
-                            if *index < elements.len() {
-                                let projected = ConformValue::certain(elements[*index].clone());
-                                state.set_var(stmt.variable.clone(), projected);
-                                changed = true;
-                            } else {
-                                return Err(QuasarError::InvalidOperation(
-                                    format!("Tuple index {} out of bounds", index)
-                                ));
-                            }
+            None => new_statements.push(stmt.clone()),
+        }
+    }
+    
+    if changed {
+        state.program.statements = new_statements;
+    }
+    
+    Ok(changed)
+}
+
+pub fn rewrite_statement(state: &mut ExecutionState, stmt: &Statement) -> Result<Option<(Rule, Vec<Statement>)>> {
+    let mut expansion = Vec::new();
+    state.begin_reduction();
+    
+    let rule = match &stmt.expression {
+        Expression::Variable(src_var) => {
+            if let Some(value) = state.lookup_var(src_var) {
+                state.set_var(stmt.variable.clone(), value.clone());
+                Some(Rule::Alias)
+            } else {
+                None
+            }
+        }
+        
+        Expression::Projection { index, variable } => {
+            if let Some(value) = state.lookup_var(variable) {
+                if let Some(error) = value.failure() {
+                    state.set_var(stmt.variable.clone(), ConformValue::certain(error.clone()));
+                    Some(Rule::Err)
+                } else if let Some(certain_val) = value.as_certain() {
+                    let projected = if let Value::Tuple(elements) = certain_val {
+                        if *index < elements.len() {
+                            ConformValue::certain(elements[*index].clone())
                         } else {
-                            return Err(QuasarError::TypeError(
-                                "Projection can only be applied to tuples".to_string()
-                            ));
+                            state.recover(&stmt.variable, QuasarError::InvalidOperation(
+                                format!("Tuple index {} out of bounds", index)
+                            ))?
                         }
                     } else {
-                        new_statements.push(stmt.clone());
-                    }
+                        state.recover(&stmt.variable, QuasarError::TypeError(
+                            "Projection can only be applied to tuples".to_string()
+                        ))?
+                    };
+                    state.set_var(stmt.variable.clone(), projected);
+                    Some(Rule::Proj)
                 } else {
-                    new_statements.push(stmt.clone());
+                    None
                 }
+            } else {
+                None
             }
-            
-            Expression::If { condition, then_block, else_block } => {
-                if let Some(cond_value) = state.lookup_var(condition) {
-                    let has_true = cond_value.possibilities.contains(&Value::Primitive(PrimitiveValue::Boolean(true)));
-                    let has_false = cond_value.possibilities.contains(&Value::Primitive(PrimitiveValue::Boolean(false)));
-                    
-                    if has_true && !has_false {
-                        expand_block(&stmt.variable, then_block, &mut new_statements);
-                        changed = true;
-                    } else if !has_true && has_false {
-                        if let Some(else_block) = else_block {
-                            expand_block(&stmt.variable, else_block, &mut new_statements);
-                            changed = true;
-                        }
-                    } else if has_true && has_false {
-                        let then_result_var = format!("{}_then", stmt.variable);
-                        let else_result_var = format!("{}_else", stmt.variable);
-                        
-                        expand_block(&then_result_var, then_block, &mut new_statements);
-                        if let Some(else_block) = else_block {
-                            expand_block(&else_result_var, else_block, &mut new_statements);
-                        }
-                        
-                        new_statements.push(Statement {
-                            variable: stmt.variable.clone(),
-                            expression: Expression::Join(vec![then_result_var, else_result_var]),
-                        });
-                        changed = true;
+        }
+        
+        Expression::If { condition, then_block, else_block } => {
+            if let Some(error) = state.lookup_var(condition).and_then(|v| v.failure()) {
+                state.set_var(stmt.variable.clone(), ConformValue::certain(error.clone()));
+                Some(Rule::Err)
+            } else if let Some(cond_value) = state.lookup_var(condition) {
+                let has_true = cond_value.possibilities.contains(&Value::Primitive(PrimitiveValue::Boolean(true)));
+                let has_false = cond_value.possibilities.contains(&Value::Primitive(PrimitiveValue::Boolean(false)));
+                
+                if has_true && !has_false {
+                    expand_block(&stmt.variable, then_block, &mut expansion);
+                    Some(Rule::IfT)
+                } else if !has_true && has_false {
+                    if let Some(else_block) = else_block {
+                        expand_block(&stmt.variable, else_block, &mut expansion);
+                        Some(Rule::IfF)
                     } else {
-                        new_statements.push(stmt.clone());
+                        None
+                    }
+                } else if has_true && has_false {
+                    let then_result_var = format!("{}_then", stmt.variable);
+                    let else_result_var = format!("{}_else", stmt.variable);
+                    
+                    expand_block(&then_result_var, then_block, &mut expansion);
+                    if let Some(else_block) = else_block {
+                        expand_block(&else_result_var, else_block, &mut expansion);
                     }
+                    
+                    expansion.push(Statement {
+                        variable: stmt.variable.clone(),
+                        expression: Expression::Join(vec![then_result_var, else_result_var]),
+                    });
+                    Some(Rule::IfTf)
                 } else {
-                    new_statements.push(stmt.clone());
+                    None
+                }
+            } else {
+                None
+            }
+        }
+        
+        Expression::ExternalCall { function, argument } => match state.lookup_var(argument) {
+            Some(value) if state.fans_out(value) => {
+                let mut cases = Vec::new();
+                for (i, possibility) in value.possibilities.clone().into_iter().enumerate() {
+                    let argument_var = format!("{}_arg_{}", stmt.variable, i);
+                    let case_var = format!("{}_case_{}", stmt.variable, i);
+                    state.set_var(argument_var.clone(), ConformValue::certain(possibility));
+                    expansion.push(Statement {
+                        variable: case_var.clone(),
+                        expression: Expression::ExternalCall {
+                            function: function.clone(),
+                            argument: argument_var,
+                        },
+                    });
+                    cases.push(case_var);
                 }
+                expansion.push(Statement {
+                    variable: stmt.variable.clone(),
+                    expression: Expression::Join(cases),
+                });
+                Some(Rule::Fan)
+            }
+            _ => None,
+        },
+        
+        Expression::Fold { list, initial, block } => rewrite_fold(state, stmt, list, initial, block, &mut expansion)?,
+        
+        Expression::Try { body, handler } => {
+            let try_var = reserved_name(&stmt.variable, "try");
+            expand_block(&try_var, body, &mut expansion);
+            for expanded in &expansion {
+                state.protect_var(expanded.variable.clone());
             }
             
-            Expression::Fold { list, initial, block } => {
-                let should_expand = if let (Some(list_value), Some(initial_value)) = (state.lookup_var(list), state.lookup_var(initial)) {
-                    if let (Some(certain_list), Some(_)) = (list_value.as_certain(), initial_value.as_certain()) {
-                        matches!(certain_list, Value::List(_))
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                };
+            expansion.push(Statement {
+                variable: stmt.variable.clone(),
+                expression: Expression::Catch {
+                    value: try_var,
+                    handler: handler.clone(),
+                },
+            });
+            Some(Rule::Try)
+        }
+        
+        Expression::Catch { value, handler } => {
+            if let Some(result) = state.lookup_var(value).cloned() {
+                let (errors, values): (Vec<Value>, Vec<Value>) = result.possibilities
+                    .into_iter()
+                    .partition(|v| v.is_failure());
                 
-                if should_expand {
-                    let list_value = state.lookup_var(list).unwrap();
-                    let certain_list = list_value.as_certain().unwrap();
-                    if let Value::List(elements) = certain_list {
-                        let elements_clone = elements.clone();
-                        let mut accumulator_var = initial.clone();
-                        
-                        for (i, element) in elements_clone.iter().enumerate() {
-                            let iter_var = format!("{}_iter_{}", stmt.variable, i);
-                            let acc_var = format!("{}_acc_{}", stmt.variable, i);
-                            
-                            state.set_var(iter_var.clone(), ConformValue::certain(element.clone()));
-                            
-                            let tuple_var = format!("{}_tuple_{}", stmt.variable, i);
-                            new_statements.push(Statement {
-                                variable: tuple_var.clone(),
-                                expression: Expression::Tuple(vec![accumulator_var.clone(), iter_var]),
-                            });
-                            
-                            expand_block_with_param(&acc_var, block, &tuple_var, &mut new_statements);
-                            
-                            accumulator_var = acc_var;
-                        }
-                        
-                        new_statements.push(Statement {
+                if errors.is_empty() {
+                    state.set_var(stmt.variable.clone(), ConformValue::uncertain(values));
+                } else {
+                    let error_var = reserved_name(&stmt.variable, "error");
+                    let messages = errors.into_iter().map(|e| match e {
+                        Value::Error(message) => Value::Primitive(PrimitiveValue::String(message)),
+                        Value::Rejected(function) => Value::Primitive(PrimitiveValue::String(
+                            format!("Call to {} was rejected by the user", function)
+                        )),
+                        other => other,
+                    });
+                    state.set_var(error_var.clone(), ConformValue::uncertain(messages));
+                    
+                    if values.is_empty() {
+                        expand_block_with_param(&stmt.variable, handler, &error_var, &mut expansion);
+                    } else {
+                        let ok_var = reserved_name(&stmt.variable, "ok");
+                        let handler_var = reserved_name(&stmt.variable, "handler");
+                        state.set_var(ok_var.clone(), ConformValue::uncertain(values));
+                        expand_block_with_param(&handler_var, handler, &error_var, &mut expansion);
+                        expansion.push(Statement {
                             variable: stmt.variable.clone(),
-                            expression: Expression::Variable(accumulator_var),
+                            expression: Expression::Join(vec![ok_var, handler_var]),
                         });
-                        changed = true;
                     }
-                } else {
-                    new_statements.push(stmt.clone());
                 }
-            }
-            
-            Expression::PendingCall(_call_id) => {
-                new_statements.push(stmt.clone());
-            }
-            
-            _ => {
-                new_statements.push(stmt.clone());
+                Some(Rule::Catch)
+            } else {
+                None
             }
         }
+        
+        _ => None,
+    };
+    
+    let Some(rule) = rule else {
+        return Ok(None);
+    };
+    
+    if matches!(stmt.expression, Expression::If { .. }) {
+        state.settle_speculation(
+            &stmt.variable,
+            matches!(rule, Rule::IfT | Rule::IfTf),
+            matches!(rule, Rule::IfF | Rule::IfTf),
+        );
     }
     
-    if changed {
-        state.program.statements = new_statements;
+    if state.is_protected(&stmt.variable) {
+        for expanded in &expansion {
+            state.protect_var(expanded.variable.clone());
+        }
     }
     
-    Ok(changed)
+    state.record_reduction(rule, stmt, &expansion)?;
+    Ok(Some((rule, expansion)))
+}
+
+fn rewrite_fold(
+    state: &mut ExecutionState,
+    stmt: &Statement,
+    list: &str,
+    initial: &str,
+    block: &Block,
+    expansion: &mut Vec<Statement>,
+) -> Result<Option<Rule>> {
+    let error = [list, initial].iter()
+        .find_map(|var| state.lookup_var(var).and_then(|v| v.failure()))
+        .cloned();
+    if let Some(error) = error {
+        state.set_var(stmt.variable.clone(), ConformValue::certain(error));
+        return Ok(Some(Rule::Err));
+    }
+    
+    if let Some(Some(non_list)) = state.lookup_var(list).map(|v| v.as_certain()) {
+        if !matches!(non_list, Value::List(_)) {
+            let value = state.recover(&stmt.variable, QuasarError::TypeError(
+                "Fold can only be applied to lists".to_string()
+            ))?;
+            state.set_var(stmt.variable.clone(), value);
+            return Ok(Some(Rule::Err));
+        }
+    }
+    
+    let should_expand = if let (Some(list_value), Some(initial_value)) = (state.lookup_var(list), state.lookup_var(initial)) {
+        if let (Some(certain_list), Some(_)) = (list_value.as_certain(), initial_value.as_certain()) {
+            matches!(certain_list, Value::List(_))
+        } else {
+            false
+        }
+    } else {
+        false
+    };
+    
+    if !should_expand {
+        return Ok(None);
+    }
+    
+    let list_value = state.lookup_var(list).unwrap();
+    let certain_list = list_value.as_certain().unwrap();
+    let Value::List(elements) = certain_list else {
+        return Ok(None);
+    };
+    
+    let elements_clone = elements.clone();
+    let mut accumulator_var = initial.to_string();
+    
+    for (i, element) in elements_clone.iter().enumerate() {
+        let iter_var = format!("{}_iter_{}", stmt.variable, i);
+        let acc_var = format!("{}_acc_{}", stmt.variable, i);
+        
+        state.set_var(iter_var.clone(), ConformValue::certain(element.clone()));
+        
+        let tuple_var = format!("{}_tuple_{}", stmt.variable, i);
+        expansion.push(Statement {
+            variable: tuple_var.clone(),
+            expression: Expression::Tuple(vec![accumulator_var.clone(), iter_var]),
+        });
+        
+        expand_block_with_param(&acc_var, block, &tuple_var, expansion);
+        
+        accumulator_var = acc_var;
+    }
+    
+    expansion.push(Statement {
+        variable: stmt.variable.clone(),
+        expression: Expression::Variable(accumulator_var),
+    });
+    
+    Ok(Some(Rule::Fold))
 }
 
 pub async fn check_pending_calls(state: &mut ExecutionState) -> Result<bool> {
@@ -152,27 +298,74 @@ pub async fn check_pending_calls(state: &mut ExecutionState) -> Result<bool> {
     }
     
     for index in completed_indices.into_iter().rev() {
-        let pc = state.pending_calls.remove(index);
-        match pc.handle.await {
-            Ok(Ok(result)) => {
-                let var_name = pc.assignment_var.clone();
-                state.set_var(var_name.clone(), result);
-                
-                for stmt in &mut state.program.statements {
-                    if let Expression::PendingCall(id) = &stmt.expression {
-                        if id == &pc.id {
-                            stmt.expression = Expression::Variable(var_name.clone());
-                            changed = true;
-                            break;
-                        }
-                    }
-                }
+        changed |= complete_pending_call(state, index).await?;
+    }
+    
+    Ok(changed)
+}
+
+pub async fn complete_pending_call(state: &mut ExecutionState, index: usize) -> Result<bool> {
+    let mut changed = false;
+    let pc = state.pending_calls.remove(index);
+    state.begin_reduction();
+    let outcome = match pc.handle.await {
+        Ok(outcome) => outcome,
+        Err(_) => Err(QuasarError::RuntimeError("Task panicked".to_string())),
+    };
+    if let Some(recorder) = &state.options.recorder {
+        recorder.record(&pc.id, &pc.function, &pc.argument, &outcome)?;
+    }
+    
+    let key = call_key(&pc.function, &pc.argument);
+    let speculative = state.is_speculative(&pc.id);
+    let shared = state.inflight_calls.remove(&key).is_some();
+    
+    let mut bound_vars = if speculative { Vec::new() } else { vec![pc.assignment_var.clone()] };
+    for stmt in &state.program.statements {
+        if stmt.expression == Expression::PendingCall(pc.id.clone()) && !bound_vars.contains(&stmt.variable) {
+            bound_vars.push(stmt.variable.clone());
+        }
+    }
+    
+    let result = match outcome {
+        Ok(result) => {
+            if shared {
+                state.completed_calls.insert(key, result.clone());
+            }
+            if let Some(Err(e)) = state.result_cache().map(|cache| cache.put(&pc.function, &pc.argument, &result)) {
+                state.log(&format!("Cache: {}", e));
             }
-            Ok(Err(e)) => return Err(e),
-            Err(_) => return Err(QuasarError::RuntimeError("Task panicked".to_string())),
+            result
+        }
+        Err(e) if speculative => {
+            state.log(&format!("Speculative {} call {} failed: {}", pc.function, pc.id, e));
+            state.discard_speculation(&key);
+            return Ok(false);
+        }
+        Err(e) => state.handle_call_failure(&bound_vars, &pc.function, &pc.argument, e)?,
+    };
+    
+    for stmt in &mut state.program.statements {
+        if stmt.expression == Expression::PendingCall(pc.id.clone()) {
+            stmt.expression = Expression::Variable(stmt.variable.clone());
+            changed = true;
         }
     }
     
+    for var_name in bound_vars {
+        state.set_var(var_name, result.clone());
+    }
+    
+    let before = Statement {
+        variable: pc.assignment_var.clone(),
+        expression: Expression::PendingCall(pc.id.clone()),
+    };
+    let after = Statement {
+        variable: pc.assignment_var.clone(),
+        expression: Expression::Variable(pc.assignment_var.clone()),
+    };
+    state.record_reduction(Rule::Ext, &before, &[after])?;
+    
     Ok(changed)
 }
 
@@ -188,7 +381,7 @@ fn expand_block(result_var: &str, block: &Block, statements: &mut Vec<Statement>
 
 fn expand_block_with_param(result_var: &str, block: &Block, param_var: &str, statements: &mut Vec<Statement>) {
     let param_subst = |var: &str| {
-        if var == &block.parameter {
+        if var == block.parameter {
             param_var.to_string()
         } else {
             var.to_string()
@@ -233,14 +426,41 @@ where
         Expression::Fold { list, initial, block } => Expression::Fold {
             list: subst(list),
             initial: subst(initial),
-            block: block.clone(),
+            block: substitute_block(block, subst),
         },
         Expression::If { condition, then_block, else_block } => Expression::If {
             condition: subst(condition),
-            then_block: then_block.clone(),
-            else_block: else_block.clone(),
+            then_block: substitute_block(then_block, subst),
+            else_block: else_block.as_ref().map(|block| substitute_block(block, subst)),
+        },
+        Expression::Try { body, handler } => Expression::Try {
+            body: substitute_block(body, subst),
+            handler: substitute_block(handler, subst),
+        },
+        Expression::Catch { value, handler } => Expression::Catch {
+            value: subst(value),
+            handler: substitute_block(handler, subst),
         },
         Expression::Join(vars) => Expression::Join(vars.iter().map(|v| subst(v)).collect()),
         _ => expr.clone(),
     }
+}
+
+fn substitute_block<F>(block: &Block, subst: &F) -> Block
+where
+    F: Fn(&str) -> String
+{
+    if subst(&block.parameter) != block.parameter {
+        return block.clone();
+    }
+    
+    let defines_return = block.body.iter().any(|stmt| stmt.variable == block.return_var);
+    Block {
+        parameter: block.parameter.clone(),
+        body: block.body.iter().map(|stmt| Statement {
+            variable: stmt.variable.clone(),
+            expression: substitute_expression(&stmt.expression, subst),
+        }).collect(),
+        return_var: if defines_return { block.return_var.clone() } else { subst(&block.return_var) },
+    }
 }
\ No newline at end of file
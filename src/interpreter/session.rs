@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::ast::*;
+use crate::runtime::{ExecutionOptions, ExecutionReport, ExecutionState, FunctionRegistry, QuasarError, Result};
+use crate::interpreter::{infer_types, run_until_return};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionVariable {
+    pub name: String,
+    pub value_type: ValueType,
+    pub value: Option<ConformValue>,
+}
+
+pub struct Session {
+    state: ExecutionState,
+    variables: BTreeMap<String, ValueType>,
+    turns: usize,
+}
+
+impl Session {
+    pub fn new(registry: Arc<FunctionRegistry>, options: ExecutionOptions) -> Self {
+        let program = Program {
+            inputs: Vec::new(),
+            statements: Vec::new(),
+            return_var: String::new(),
+        };
+        Session {
+            state: ExecutionState::with_registry(program, registry).with_options(options),
+            variables: BTreeMap::new(),
+            turns: 0,
+        }
+    }
+
+    pub fn registry(&self) -> Arc<FunctionRegistry> {
+        self.state.registry.clone()
+    }
+
+    pub fn state(&self) -> &ExecutionState {
+        &self.state
+    }
+
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+
+    pub async fn run(&mut self, program: Program) -> Result<ExecutionReport> {
+        self.run_with_inputs(program, HashMap::new()).await
+    }
+
+    pub async fn run_with_inputs(
+        &mut self,
+        program: Program,
+        inputs: HashMap<String, Value>,
+    ) -> Result<ExecutionReport> {
+        let env = self.variables.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        let types = infer_types(&program, &self.state.registry, env)?;
+        
+        let defined: Vec<String> = program.inputs.iter().map(|input| input.name.clone())
+            .chain(program.statements.iter().map(|stmt| stmt.variable.clone()))
+            .collect();
+        if let Some(name) = defined.iter().find(|name| self.is_pending(name)) {
+            return Err(QuasarError::InvalidOperation(format!(
+                "Variable {} is still being computed by an earlier turn", name
+            )));
+        }
+        
+        let previous: Vec<_> = defined.iter()
+            .map(|name| (name.clone(), self.state.scope.get(name).cloned(), self.variables.get(name).cloned()))
+            .collect();
+        let (failures, rejected) = (self.state.failures.len(), self.state.rejected.len());
+        
+        let previous_inputs = std::mem::replace(&mut self.state.program.inputs, program.inputs);
+        if let Err(e) = self.state.bind_inputs(inputs) {
+            self.state.program.inputs = previous_inputs;
+            return Err(e);
+        }
+        for stmt in &program.statements {
+            self.state.scope.remove(&stmt.variable);
+        }
+        self.state.program.statements.extend(program.statements);
+        self.state.program.return_var = program.return_var;
+        
+        for name in &defined {
+            let value_type = types.get(name).cloned().unwrap_or(ValueType::Any);
+            self.variables.insert(name.clone(), value_type);
+        }
+        self.turns += 1;
+        
+        if let Err(e) = run_until_return(&mut self.state).await {
+            self.roll_back(&defined, previous_inputs, previous);
+            return Err(e);
+        }
+        let mut report = self.state.report();
+        report.failures.drain(..failures);
+        report.rejected.drain(..rejected);
+        Ok(report)
+    }
+
+    /// Restores the inputs and every variable the failed turn left without a value,
+    /// keeping the ones it did compute and the calls still in flight.
+    fn roll_back(
+        &mut self,
+        defined: &[String],
+        inputs: Vec<Input>,
+        previous: Vec<(String, Option<ConformValue>, Option<ValueType>)>,
+    ) {
+        let pending: Vec<String> = self.state.pending_calls.iter().map(|pc| pc.id.clone()).collect();
+        self.state.program.statements.retain(|stmt| match &stmt.expression {
+            Expression::PendingCall(id) => pending.contains(id),
+            _ => !defined.contains(&stmt.variable),
+        });
+        let inputs = std::mem::replace(&mut self.state.program.inputs, inputs);
+        for (name, value, value_type) in previous {
+            let input = inputs.iter().any(|input| input.name == name);
+            if self.is_pending(&name) || (!input && self.state.lookup_var(&name).is_some()) {
+                continue;
+            }
+            match value {
+                Some(value) => self.state.scope.insert(name.clone(), value),
+                None => self.state.scope.remove(&name),
+            };
+            match value_type {
+                Some(value_type) => self.variables.insert(name, value_type),
+                None => self.variables.remove(&name),
+            };
+        }
+    }
+
+    pub fn is_pending(&self, name: &str) -> bool {
+        self.state.program.statements.iter().any(|stmt| stmt.variable == name)
+    }
+
+    pub fn variables(&self) -> Vec<SessionVariable> {
+        self.variables.iter().map(|(name, value_type)| {
+            let value = self.state.lookup_var(name).cloned();
+            SessionVariable {
+                name: name.clone(),
+                value_type: value.as_ref().map_or(value_type.clone(), ValueType::of_conform),
+                value,
+            }
+        }).collect()
+    }
+
+    pub fn available(&self) -> Vec<SessionVariable> {
+        self.variables().into_iter().filter(|var| var.value.is_some()).collect()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&ConformValue> {
+        if self.variables.contains_key(name) {
+            self.state.lookup_var(name)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{ExecutionStatus, FailureMode};
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::syntax::parse_program;
+
+    fn session(functions: &[&TestFunction]) -> Session {
+        Session::new(Arc::new(registry(functions)), ExecutionOptions::new(false).with_quiet(true))
+    }
+
+    #[tokio::test]
+    async fn failed_turn_can_be_redefined() {
+        let broken = TestFunction::failing("broken", ValueType::String);
+        let echo = TestFunction::echo("echo");
+        let mut session = session(&[&broken, &echo]);
+        
+        let failed = session.run(parse_program("a = \"q\"; x = broken(a); return x").unwrap()).await;
+        assert!(failed.is_err());
+        assert!(!session.is_pending("x"));
+        
+        let report = session.run(parse_program("x = echo(a); return x").unwrap()).await.unwrap();
+        assert_eq!(report.status, ExecutionStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn invalid_inputs_leave_the_session_unchanged() {
+        let mut session = session(&[]);
+        let program = parse_program("input n: Integer; x = n; return x").unwrap();
+        let inputs = HashMap::from([("n".to_string(), Value::Primitive(PrimitiveValue::String("q".to_string())))]);
+        
+        assert!(session.run_with_inputs(program, inputs).await.is_err());
+        assert!(session.state().program.inputs.is_empty());
+        assert!(session.variables().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn failed_redefinition_keeps_the_old_value() {
+        let broken = TestFunction::failing("broken", ValueType::String);
+        let mut session = session(&[&broken]);
+        session.run(parse_program("x = \"old\"; return x").unwrap()).await.unwrap();
+        
+        let failed = session.run(parse_program("a = \"q\"; x = broken(a); return x").unwrap()).await;
+        assert!(failed.is_err());
+        let old = ConformValue::certain(Value::Primitive(PrimitiveValue::String("old".to_string())));
+        assert_eq!(session.lookup("x"), Some(&old));
+        assert_eq!(session.variables()[0].value_type, ValueType::String);
+    }
+
+    #[tokio::test]
+    async fn failed_turn_restores_earlier_inputs() {
+        let broken = TestFunction::failing("broken", ValueType::String);
+        let mut session = session(&[&broken]);
+        let string = |s: &str| Value::Primitive(PrimitiveValue::String(s.to_string()));
+        
+        let program = parse_program("input n: String; return n").unwrap();
+        session.run_with_inputs(program, HashMap::from([("n".to_string(), string("first"))])).await.unwrap();
+        let program = parse_program("input n: String; x = broken(n); return x").unwrap();
+        let failed = session.run_with_inputs(program, HashMap::from([("n".to_string(), string("second"))])).await;
+        
+        assert!(failed.is_err());
+        assert_eq!(session.lookup("n"), Some(&ConformValue::certain(string("first"))));
+        assert_eq!(session.state().program.inputs.len(), 1);
+        assert!(session.lookup("x").is_none());
+    }
+
+    #[tokio::test]
+    async fn report_covers_only_the_current_turn() {
+        let broken = TestFunction::failing("broken", ValueType::String);
+        let options = ExecutionOptions::new(false).with_quiet(true).with_failure_mode(FailureMode::BindError);
+        let mut session = Session::new(Arc::new(registry(&[&broken])), options);
+        
+        let report = session.run(parse_program("a = \"q\"; x = broken(a); return x").unwrap()).await.unwrap();
+        assert_eq!(report.failures.len(), 1);
+        let report = session.run(parse_program("y = a; return y").unwrap()).await.unwrap();
+        assert!(report.failures.is_empty());
+        assert_eq!(session.state().failures.len(), 1);
+    }
+}
@@ -0,0 +1,316 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Duration};
+use crate::ast::*;
+use crate::runtime::{ExecutionState, ExecutionStatus, ExecutionOptions, FunctionRegistry, Result, Rule};
+use crate::interpreter::{
+    check_program, complete_pending_call, dispatch_calls, dispatch_speculative_calls, evaluate_statement,
+    find_dispatchable_calls, find_speculative_calls, rewrite_statement,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub rule: Rule,
+    pub before: Statement,
+    pub after: Vec<Statement>,
+}
+
+pub struct Stepper {
+    state: ExecutionState,
+}
+
+impl Stepper {
+    pub fn new(program: Program, registry: Arc<FunctionRegistry>, options: ExecutionOptions) -> Result<Self> {
+        check_program(&program, &registry)?;
+        let state = ExecutionState::with_registry(program, registry).with_options(options);
+        Ok(Stepper::from_state(state))
+    }
+
+    pub fn from_state(state: ExecutionState) -> Self {
+        Stepper { state }
+    }
+
+    pub fn state(&self) -> &ExecutionState {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut ExecutionState {
+        &mut self.state
+    }
+
+    pub fn into_state(self) -> ExecutionState {
+        self.state
+    }
+
+    pub async fn step(&mut self) -> Result<Option<Step>> {
+        if let Some(step) = self.try_step().await? {
+            return Ok(Some(step));
+        }
+        
+        let cancellation = self.state.options.cancellation.clone();
+        while !self.state.pending_calls.is_empty() && !cancellation.is_cancelled() {
+            if let Some(step) = self.try_ext().await? {
+                return Ok(Some(step));
+            }
+            tokio::select! {
+                _ = sleep(Duration::from_millis(10)) => {}
+                _ = cancellation.cancelled() => {}
+            }
+        }
+        
+        self.state.status = if cancellation.is_cancelled() {
+            self.state.abort_pending_calls();
+            ExecutionStatus::Cancelled
+        } else if self.state.lookup_var(&self.state.program.return_var).is_some() {
+            ExecutionStatus::Completed
+        } else {
+            ExecutionStatus::Stuck
+        };
+        if let Some(replay) = self.state.registry.replay() {
+            if self.state.status != ExecutionStatus::Cancelled {
+                replay.verify()?;
+            }
+        }
+        Ok(None)
+    }
+
+    pub async fn try_step(&mut self) -> Result<Option<Step>> {
+        if self.state.is_cancelled() {
+            return Ok(None);
+        }
+        
+        if let Some(step) = self.try_ext().await? {
+            return Ok(Some(step));
+        }
+        if let Some(step) = self.try_internal()? {
+            return Ok(Some(step));
+        }
+        if let Some(step) = self.try_disp().await? {
+            return Ok(Some(step));
+        }
+        self.try_spec()
+    }
+
+    async fn try_ext(&mut self) -> Result<Option<Step>> {
+        let Some(index) = self.state.pending_calls.iter().position(|pc| pc.handle.is_finished()) else {
+            return Ok(None);
+        };
+        
+        let pc = &self.state.pending_calls[index];
+        let variable = pc.assignment_var.clone();
+        let before = Statement {
+            variable: variable.clone(),
+            expression: Expression::PendingCall(pc.id.clone()),
+        };
+        
+        complete_pending_call(&mut self.state, index).await?;
+        
+        Ok(Some(Step {
+            rule: Rule::Ext,
+            before,
+            after: vec![Statement {
+                variable: variable.clone(),
+                expression: Expression::Variable(variable),
+            }],
+        }))
+    }
+
+    fn try_internal(&mut self) -> Result<Option<Step>> {
+        for index in 0..self.state.program.statements.len() {
+            let stmt = self.state.program.statements[index].clone();
+            
+            if let Some((rule, replacement)) = rewrite_statement(&mut self.state, &stmt)? {
+                self.state.program.statements.splice(index..=index, replacement.clone());
+                return Ok(Some(Step { rule, before: stmt, after: replacement }));
+            }
+            
+            if let Some(rule) = evaluate_statement(&mut self.state, &stmt)? {
+                self.state.program.statements.remove(index);
+                return Ok(Some(Step { rule, before: stmt, after: vec![] }));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn try_disp(&mut self) -> Result<Option<Step>> {
+        let Some(call) = find_dispatchable_calls(&self.state).into_iter().next() else {
+            return Ok(None);
+        };
+        let Some(index) = self.state.program.statements.iter().position(|stmt| {
+            stmt.variable == call.assignment_var && matches!(stmt.expression, Expression::ExternalCall { .. })
+        }) else {
+            return Ok(None);
+        };
+        
+        let before = self.state.program.statements[index].clone();
+        let with_approval = self.state.options.with_approval;
+        dispatch_calls(&mut self.state, vec![call], with_approval).await?;
+        let after = self.state.program.statements[index].clone();
+        
+        Ok(Some(Step {
+            rule: Rule::Disp,
+            before,
+            after: vec![after],
+        }))
+    }
+
+    fn try_spec(&mut self) -> Result<Option<Step>> {
+        let mut candidates = find_speculative_calls(&self.state);
+        let Some(if_var) = candidates.first().map(|candidate| candidate.if_var.clone()) else {
+            return Ok(None);
+        };
+        candidates.retain(|candidate| candidate.if_var == if_var);
+        if !dispatch_speculative_calls(&mut self.state, candidates)? {
+            return Ok(None);
+        }
+        
+        let Some(stmt) = self.state.program.statements.iter().find(|stmt| stmt.variable == if_var).cloned() else {
+            return Ok(None);
+        };
+        Ok(Some(Step {
+            rule: Rule::Spec,
+            before: stmt.clone(),
+            after: vec![stmt],
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::syntax::parse_program;
+
+    async fn run(source: &str, registry: FunctionRegistry, options: ExecutionOptions) -> (Vec<Rule>, ExecutionState) {
+        let program = parse_program(source).unwrap();
+        let mut stepper = Stepper::new(program, Arc::new(registry), options.with_quiet(true)).unwrap();
+        let mut rules = Vec::new();
+        while let Some(step) = stepper.step().await.unwrap() {
+            rules.push(step.rule);
+        }
+        (rules, stepper.into_state())
+    }
+
+    fn options() -> ExecutionOptions {
+        ExecutionOptions::new(false)
+    }
+
+    fn result(state: &ExecutionState) -> Value {
+        assert_eq!(state.status, ExecutionStatus::Completed);
+        state.lookup_var(&state.program.return_var).unwrap().as_certain().unwrap().clone()
+    }
+
+    fn string(text: &str) -> Value {
+        Value::Primitive(PrimitiveValue::String(text.to_string()))
+    }
+
+    fn integer(value: i64) -> Value {
+        Value::Primitive(PrimitiveValue::Integer(value))
+    }
+
+    #[tokio::test]
+    async fn alias() {
+        let (rules, state) = run("x = 1; y = x; return y", FunctionRegistry::new(), options()).await;
+        
+        assert!(rules.contains(&Rule::Alias));
+        assert_eq!(result(&state), integer(1));
+    }
+
+    #[tokio::test]
+    async fn proj() {
+        let source = "a = 1; b = \"s\"; t = (a, b); x = proj 1 t; return x";
+        let (rules, state) = run(source, FunctionRegistry::new(), options()).await;
+        
+        assert!(rules.contains(&Rule::Tuple));
+        assert!(rules.contains(&Rule::Proj));
+        assert_eq!(result(&state), string("s"));
+    }
+
+    #[tokio::test]
+    async fn if_t() {
+        let source = "c = true; x = if c { r = 1; return r } else { r = 2; return r }; return x";
+        let (rules, state) = run(source, FunctionRegistry::new(), options()).await;
+        
+        assert!(rules.contains(&Rule::IfT));
+        assert_eq!(result(&state), integer(1));
+    }
+
+    #[tokio::test]
+    async fn if_f() {
+        let source = "c = false; x = if c { r = 1; return r } else { r = 2; return r }; return x";
+        let (rules, state) = run(source, FunctionRegistry::new(), options()).await;
+        
+        assert!(rules.contains(&Rule::IfF));
+        assert_eq!(result(&state), integer(2));
+    }
+
+    #[tokio::test]
+    async fn join_and_if_tf() {
+        let source = "a = true; b = false; c = join {a, b}; x = if c { r = 1; return r } else { r = 2; return r }; return x";
+        let (rules, state) = run(source, FunctionRegistry::new(), options()).await;
+        
+        assert!(rules.contains(&Rule::Join));
+        assert!(rules.contains(&Rule::IfTf));
+        let x = state.lookup_var("x").unwrap();
+        assert_eq!(x, &ConformValue::uncertain([integer(1), integer(2)]));
+    }
+
+    #[tokio::test]
+    async fn fold() {
+        let source = "xs = [1, 2]; init = 0; r = fold xs init {p => e = proj 1 p; return e}; return r";
+        let (rules, state) = run(source, FunctionRegistry::new(), options()).await;
+        
+        assert!(rules.contains(&Rule::Fold));
+        assert_eq!(result(&state), integer(2));
+    }
+
+    #[tokio::test]
+    async fn disp_and_ext() {
+        let echo = TestFunction::echo("echo");
+        let (rules, state) = run("a = \"q\"; x = echo(a); return x", registry(&[&echo]), options()).await;
+        
+        let disp = rules.iter().position(|rule| *rule == Rule::Disp).unwrap();
+        let ext = rules.iter().position(|rule| *rule == Rule::Ext).unwrap();
+        assert!(disp < ext);
+        assert_eq!(echo.calls(), 1);
+        assert_eq!(result(&state), string("q"));
+    }
+
+    #[tokio::test]
+    async fn try_and_catch() {
+        let broken = TestFunction::failing("broken", ValueType::Any);
+        let source = "a = \"q\"; y = try { r = broken(a); return r } else { e => return e }; return y";
+        let (rules, state) = run(source, registry(&[&broken]), options()).await;
+        
+        assert!(rules.contains(&Rule::Try));
+        assert!(rules.contains(&Rule::Catch));
+        assert!(matches!(result(&state), Value::Primitive(PrimitiveValue::String(_))));
+    }
+
+    #[tokio::test]
+    async fn fan() {
+        let echo = TestFunction::echo("echo");
+        let source = "a = \"x\"; b = \"y\"; c = join {a, b}; r = echo(c); return r";
+        let (rules, state) = run(source, registry(&[&echo]), options().with_fan_out(2)).await;
+        
+        assert!(rules.contains(&Rule::Fan));
+        assert_eq!(echo.calls(), 2);
+        assert_eq!(state.lookup_var("r").unwrap(), &ConformValue::uncertain([string("x"), string("y")]));
+    }
+
+    #[tokio::test]
+    async fn spec() {
+        let flag = TestFunction::constant("flag", Value::Primitive(PrimitiveValue::Boolean(true)))
+            .with_delay(Duration::from_millis(50));
+        let left = TestFunction::echo("left");
+        let right = TestFunction::echo("right");
+        let source = "a = \"q\"; c = flag(a); x = if c { r = left(a); return r } else { r = right(a); return r }; return x";
+        let (rules, state) = run(source, registry(&[&flag, &left, &right]), options().with_speculation(true)).await;
+        
+        assert!(rules.contains(&Rule::Spec));
+        assert_eq!(left.calls(), 1);
+        assert_eq!(state.speculation.used, 1);
+        assert_eq!(state.speculation.dispatched, 2);
+        assert_eq!(result(&state), string("q"));
+    }
+}
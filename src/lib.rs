@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod interpreter;
 pub mod runtime;
+pub mod syntax;
 
 pub use ast::*;
\ No newline at end of file
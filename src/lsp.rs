@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use serde_json::{json, Value as Json};
+use quasar::ast::*;
+use quasar::interpreter::infer_types;
+use quasar::runtime::{FunctionRegistry, QuasarError, Result};
+use quasar::syntax::{parse_program_with_symbols, parse_program_with_trivia, print_program_with_trivia, Symbol, SymbolKind};
+use crate::cli::{EXIT_COMPLETED, EXIT_ERROR};
+use crate::wire::{spawn_stdin_reader, write_message};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const REQUEST_FAILED: i64 = -32803;
+
+struct Document {
+    text: String,
+    symbols: Vec<Symbol>,
+    types: HashMap<String, ValueType>,
+}
+
+pub struct LspServer {
+    registry: Arc<FunctionRegistry>,
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+impl LspServer {
+    pub fn new(registry: Arc<FunctionRegistry>) -> Self {
+        LspServer {
+            registry,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    pub async fn run(mut self) -> Result<i32> {
+        let mut messages = spawn_stdin_reader();
+        while let Some(message) = messages.recv().await {
+            let method = message["method"].as_str().unwrap_or_default().to_string();
+            let params = message.get("params").cloned().unwrap_or(Json::Null);
+            
+            match message.get("id") {
+                Some(id) => {
+                    let response = match self.request(&method, &params) {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err((code, message)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": code, "message": message },
+                        }),
+                    };
+                    send(&response)?;
+                }
+                None if method == "exit" => break,
+                None => self.notification(&method, &params)?,
+            }
+        }
+        
+        Ok(if self.shutdown { EXIT_COMPLETED } else { EXIT_ERROR })
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> std::result::Result<Json, (i64, String)> {
+        let failed = |e: QuasarError| (REQUEST_FAILED, e.to_string());
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": [] },
+                    "documentFormattingProvider": true,
+                },
+                "serverInfo": { "name": "quasar", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => Ok(self.definition(params)),
+            "textDocument/hover" => Ok(self.hover(params)),
+            "textDocument/completion" => Ok(self.completion(params)),
+            "textDocument/formatting" => self.formatting(params).map_err(failed),
+            _ => Err((METHOD_NOT_FOUND, format!("Unsupported method: {}", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Result<()> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default().to_string();
+                self.update(uri, text)
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array().into_iter().flatten();
+                match changes.filter_map(|change| change["text"].as_str()).next_back() {
+                    Some(text) => self.update(uri, text.to_string()),
+                    None => Ok(()),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                publish_diagnostics(&uri, Vec::new())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn update(&mut self, uri: String, text: String) -> Result<()> {
+        let diagnostics = self.diagnose(uri.clone(), text);
+        publish_diagnostics(&uri, diagnostics)
+    }
+
+    fn diagnose(&mut self, uri: String, text: String) -> Vec<Json> {
+        let mut diagnostics = Vec::new();
+        let document = self.documents.entry(uri).or_insert_with(|| Document {
+            text: String::new(),
+            symbols: Vec::new(),
+            types: HashMap::new(),
+        });
+        document.text = text;
+        
+        match parse_program_with_symbols(&document.text) {
+            Ok((program, symbols)) => {
+                match infer_types(&program, &self.registry, HashMap::new()) {
+                    Ok(types) => document.types = types,
+                    Err(e) => {
+                        let message = e.to_string();
+                        let range = symbols.iter()
+                            .filter(|symbol| matches!(symbol.kind, SymbolKind::Function | SymbolKind::Reference))
+                            .find(|symbol| mentions(&message, &symbol.name))
+                            .map_or_else(|| range(1, 1, 0), symbol_range);
+                        diagnostics.push(diagnostic(range, message));
+                    }
+                }
+                document.symbols = symbols;
+            }
+            Err(QuasarError::ParseError { line, column, message }) => {
+                diagnostics.push(diagnostic(range(line, column, 1), format!("Parse error: {}", message)));
+            }
+            Err(e) => diagnostics.push(diagnostic(range(1, 1, 0), e.to_string())),
+        }
+        diagnostics
+    }
+
+    fn symbol_at(&self, params: &Json) -> Option<(&Document, &Symbol)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let document = self.documents.get(uri)?;
+        let (line, character) = position(params)?;
+        let symbol = document.symbols.iter().find(|symbol| {
+            let start = symbol.column - 1;
+            symbol.line == line + 1 && (start..start + symbol.name.chars().count()).contains(&character)
+        })?;
+        Some((document, symbol))
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let Some((document, symbol)) = self.symbol_at(params) else {
+            return Json::Null;
+        };
+        if symbol.kind == SymbolKind::Function {
+            return Json::Null;
+        }
+        document.symbols.iter()
+            .take_while(|candidate| (candidate.line, candidate.column) <= (symbol.line, symbol.column))
+            .filter(|candidate| candidate.name == symbol.name && defines(candidate.kind))
+            .last()
+            .map_or(Json::Null, |definition| json!({
+                "uri": params["textDocument"]["uri"],
+                "range": symbol_range(definition),
+            }))
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let Some((document, symbol)) = self.symbol_at(params) else {
+            return Json::Null;
+        };
+        let contents = if symbol.kind == SymbolKind::Function {
+            match self.registry.signature(&symbol.name) {
+                Some(signature) => signature.summary(),
+                None => format!("Unknown function: {}", symbol.name),
+            }
+        } else {
+            match document.types.get(&symbol.name) {
+                Some(value_type) => format!("{}: {}", symbol.name, value_type),
+                None => return Json::Null,
+            }
+        };
+        json!({
+            "contents": { "kind": "plaintext", "value": contents },
+            "range": symbol_range(symbol),
+        })
+    }
+
+    fn completion(&self, params: &Json) -> Json {
+        let mut items = BTreeMap::new();
+        for signature in self.registry.signatures() {
+            items.insert(signature.name.clone(), json!({
+                "label": signature.name,
+                "kind": 3,
+                "detail": signature.summary(),
+            }));
+        }
+        
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        if let (Some(document), Some((line, character))) = (self.documents.get(uri), position(params)) {
+            let visible = document.symbols.iter()
+                .filter(|symbol| defines(symbol.kind) && symbol.name != "_")
+                .filter(|symbol| (symbol.line, symbol.column) < (line + 1, character + 1));
+            for symbol in visible {
+                let detail = document.types.get(&symbol.name).map(ToString::to_string);
+                items.entry(symbol.name.clone()).or_insert_with(|| json!({
+                    "label": symbol.name,
+                    "kind": 6,
+                    "detail": detail,
+                }));
+            }
+        }
+        
+        json!(items.into_values().collect::<Vec<_>>())
+    }
+
+    fn formatting(&self, params: &Json) -> Result<Json> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let document = self.documents.get(uri).ok_or_else(|| {
+            QuasarError::InvalidOperation(format!("Unknown document: {}", uri))
+        })?;
+        let (program, trivia) = parse_program_with_trivia(&document.text)?;
+        let formatted = format!("{}\n", print_program_with_trivia(&program, &trivia));
+        if formatted == document.text {
+            return Ok(json!([]));
+        }
+        
+        let lines = document.text.split('\n').count();
+        Ok(json!([{
+            "range": { "start": { "line": 0, "character": 0 }, "end": { "line": lines, "character": 0 } },
+            "newText": formatted,
+        }]))
+    }
+}
+
+fn defines(kind: SymbolKind) -> bool {
+    matches!(kind, SymbolKind::Input | SymbolKind::Definition | SymbolKind::Parameter)
+}
+
+fn mentions(message: &str, name: &str) -> bool {
+    message.contains(&format!("`{}`", name))
+        || message.ends_with(&format!(": {}", name))
+        || message.starts_with(&format!("{} ", name))
+}
+
+fn position(params: &Json) -> Option<(usize, usize)> {
+    let position = &params["position"];
+    Some((position["line"].as_u64()? as usize, position["character"].as_u64()? as usize))
+}
+
+fn range(line: usize, column: usize, length: usize) -> Json {
+    let (line, character) = (line.saturating_sub(1), column.saturating_sub(1));
+    json!({
+        "start": { "line": line, "character": character },
+        "end": { "line": line, "character": character + length },
+    })
+}
+
+fn symbol_range(symbol: &Symbol) -> Json {
+    range(symbol.line, symbol.column, symbol.name.chars().count())
+}
+
+fn diagnostic(range: Json, message: String) -> Json {
+    json!({ "range": range, "severity": 1, "source": "quasar", "message": message })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Result<()> {
+    send(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    }))
+}
+
+fn send(message: &Json) -> Result<()> {
+    write_message(&mut std::io::stdout().lock(), message).map_err(|e| {
+        QuasarError::RuntimeError(format!("Failed to write LSP message: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///test.qsr";
+
+    fn server() -> LspServer {
+        LspServer::new(Arc::new(FunctionRegistry::with_builtins()))
+    }
+
+    fn at(line: usize, character: usize) -> Json {
+        json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_error() {
+        let mut server = server();
+        assert!(server.diagnose(URI.to_string(), "a = \"q\";\nb = find(a);\nreturn b\n".to_string()).is_empty());
+        
+        let diagnostics = server.diagnose(URI.to_string(), "a = 1;\nb = ;\nreturn b\n".to_string());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["range"]["start"], json!({ "line": 1, "character": 4 }));
+        
+        let diagnostics = server.diagnose(URI.to_string(), "a = 1;\nreturn missing\n".to_string());
+        assert_eq!(diagnostics[0]["range"], range(2, 8, 7));
+    }
+
+    #[test]
+    fn definition_and_hover_follow_symbols() {
+        let mut server = server();
+        server.diagnose(URI.to_string(), "a = \"q\";\nb = find(a);\nreturn b\n".to_string());
+        
+        assert_eq!(server.definition(&at(1, 9))["range"], range(1, 1, 1));
+        assert_eq!(server.definition(&at(1, 4)), Json::Null);
+        assert_eq!(server.hover(&at(2, 7))["contents"]["value"], "b: List<String>");
+        let summary = server.registry.signature("find").unwrap().summary();
+        assert_eq!(server.hover(&at(1, 5))["contents"]["value"], summary);
+    }
+
+    #[test]
+    fn completion_offers_functions_and_earlier_definitions() {
+        let mut server = server();
+        server.diagnose(URI.to_string(), "a = \"q\";\nb = find(a);\nreturn b\n".to_string());
+        
+        let labels = |items: Json| -> Vec<String> {
+            items.as_array().unwrap().iter().map(|item| item["label"].as_str().unwrap().to_string()).collect()
+        };
+        assert_eq!(labels(server.completion(&at(1, 0))), ["a", "exists", "find", "simple_query"]);
+        assert_eq!(labels(server.completion(&at(2, 0))), ["a", "b", "exists", "find", "simple_query"]);
+    }
+
+    #[test]
+    fn formatting_replaces_unformatted_documents() {
+        let mut server = server();
+        server.diagnose(URI.to_string(), "a = 1; # one\nreturn a\n".to_string());
+        assert_eq!(server.formatting(&at(0, 0)).unwrap(), json!([]));
+        
+        server.diagnose(URI.to_string(), "a   =   1; # one\nreturn   a".to_string());
+        let edits = server.formatting(&at(0, 0)).unwrap();
+        assert_eq!(edits[0]["newText"], "a = 1; # one\nreturn a\n");
+        assert!(server.formatting(&json!({ "textDocument": { "uri": "file:///other.qsr" } })).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use quasar::ast::*;
+use quasar::interpreter::{infer_types, Step, Stepper};
+use quasar::runtime::{ExecutionOptions, ExecutionState, FunctionRegistry, QuasarError, Result};
+use quasar::syntax::{parse_statements, print_conform_value, print_statement, tokenize, TokenKind};
+
+const HELP: &str = "\
+Enter statements such as `x = \"image\";` or `y = find(x);`.
+External calls are dispatched as soon as their argument is bound.
+  
+  <var>            show the value of a variable
+  return <var>     run until <var> is bound and show it
+  :step            apply a single rewrite (waits for a pending call if needed)
+  :run             apply rewrites until nothing is left to do
+  :scope           show all bound variables
+  :pending         show pending external calls
+  :program         show statements that have not been reduced yet
+  :auto on|off     apply rewrites automatically after each input (default: on)
+  :functions       list available external functions
+  :help            show this help
+  :quit            exit
+";
+
+pub struct Repl {
+    stepper: Stepper,
+    types: HashMap<String, ValueType>,
+    auto: bool,
+}
+
+impl Repl {
+    pub fn new(registry: Arc<FunctionRegistry>, options: ExecutionOptions) -> Self {
+        let program = Program {
+            inputs: Vec::new(),
+            statements: Vec::new(),
+            return_var: String::new(),
+        };
+        let state = ExecutionState::with_registry(program, registry).with_options(options);
+        Repl {
+            stepper: Stepper::from_state(state),
+            types: HashMap::new(),
+            auto: true,
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        println!("QUASAR REPL. Type :help for commands.");
+        let mut buffer = String::new();
+        
+        loop {
+            let prompt = if buffer.is_empty() { "quasar> " } else { "   ...> " };
+            let Some(line) = read_line(prompt).await? else {
+                break;
+            };
+            
+            if buffer.is_empty() && line.trim().starts_with(':') {
+                match self.command(line.trim()).await {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(e) => {
+                        println!("Error: {}", e);
+                        continue;
+                    }
+                }
+            }
+            
+            buffer.push_str(&line);
+            if buffer.trim().is_empty() {
+                buffer.clear();
+                continue;
+            }
+            if is_incomplete(&buffer) {
+                continue;
+            }
+            
+            let input = std::mem::take(&mut buffer);
+            if let Err(e) = self.input(&input).await {
+                println!("Error: {}", e);
+            }
+        }
+        
+        Ok(())
+    }
+
+    async fn command(&mut self, command: &str) -> Result<bool> {
+        let mut parts = command.split_whitespace();
+        let name = parts.next().unwrap_or_default();
+        
+        match name {
+            ":quit" | ":q" => return Ok(false),
+            ":help" => print!("{}", HELP),
+            ":step" => match self.stepper.step().await? {
+                Some(step) => print_step(&step),
+                None => println!("Nothing to reduce."),
+            },
+            ":run" => self.run_to_end().await?,
+            ":scope" => print_scope(self.stepper.state()),
+            ":pending" => print_pending(self.stepper.state()),
+            ":program" => {
+                for stmt in &self.stepper.state().program.statements {
+                    println!("{}", print_statement(stmt));
+                }
+            }
+            ":auto" => match parts.next() {
+                Some("on") => self.auto = true,
+                Some("off") => self.auto = false,
+                _ => println!("auto is {}", if self.auto { "on" } else { "off" }),
+            },
+            ":functions" => {
+                for signature in self.stepper.state().registry.signatures() {
+                    println!("{}", signature.summary());
+                }
+            }
+            _ => println!("Unknown command {}. Type :help for commands.", name),
+        }
+        
+        Ok(true)
+    }
+
+    async fn input(&mut self, input: &str) -> Result<()> {
+        let trimmed = input.trim().trim_end_matches(';');
+        if !trimmed.is_empty() && trimmed.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return self.print_var(trimmed);
+        }
+        
+        let (statements, return_var) = parse_statements(input)?;
+        for stmt in &statements {
+            self.check_unbound(&stmt.variable)?;
+        }
+        let checked = Program {
+            inputs: Vec::new(),
+            statements: statements.clone(),
+            return_var: return_var.clone().unwrap_or_default(),
+        };
+        let types = infer_types(&checked, &self.stepper.state().registry, self.types.clone())?;
+        
+        let defined: Vec<String> = statements.iter().map(|stmt| stmt.variable.clone()).collect();
+        self.stepper.state_mut().program.statements.extend(statements);
+        self.types = types;
+        if let Err(e) = self.evaluate(return_var).await {
+            self.roll_back(&defined);
+            return Err(e);
+        }
+        
+        Ok(())
+    }
+
+    async fn evaluate(&mut self, return_var: Option<String>) -> Result<()> {
+        if self.auto {
+            self.reduce().await?;
+        }
+        
+        if let Some(return_var) = return_var {
+            self.stepper.state_mut().program.return_var = return_var.clone();
+            self.run_to_end().await?;
+            self.print_var(&return_var)?;
+        }
+        Ok(())
+    }
+
+    fn check_unbound(&self, variable: &str) -> Result<()> {
+        let state = self.stepper.state();
+        if state.lookup_var(variable).is_some() || state.program.statements.iter().any(|stmt| stmt.variable == variable) {
+            return Err(QuasarError::InvalidOperation(format!("Variable {} is already defined", variable)));
+        }
+        Ok(())
+    }
+
+    fn roll_back(&mut self, defined: &[String]) {
+        let state = self.stepper.state_mut();
+        let pending: Vec<String> = state.pending_calls.iter().map(|pc| pc.id.clone()).collect();
+        state.program.statements.retain(|stmt| match &stmt.expression {
+            Expression::PendingCall(id) => pending.contains(id),
+            _ => !defined.contains(&stmt.variable),
+        });
+        for name in defined {
+            if state.lookup_var(name).is_none() && !state.program.statements.iter().any(|stmt| stmt.variable == *name) {
+                self.types.remove(name);
+            }
+        }
+    }
+
+    async fn reduce(&mut self) -> Result<()> {
+        while let Some(step) = self.stepper.try_step().await? {
+            print_step(&step);
+        }
+        Ok(())
+    }
+
+    async fn run_to_end(&mut self) -> Result<()> {
+        while let Some(step) = self.stepper.step().await? {
+            print_step(&step);
+        }
+        Ok(())
+    }
+
+    fn print_var(&self, name: &str) -> Result<()> {
+        let state = self.stepper.state();
+        if let Some(value) = state.lookup_var(name) {
+            println!("{} = {}", name, print_conform_value(value));
+        } else if let Some(pc) = state.pending_calls.iter().find(|pc| pc.assignment_var == name) {
+            println!("{} is waiting for {} ({})", name, pc.id, pc.function);
+        } else if state.program.statements.iter().any(|stmt| stmt.variable == name) {
+            println!("{} is not reduced yet", name);
+        } else {
+            return Err(QuasarError::VariableNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+pub fn print_scope(state: &ExecutionState) {
+    let mut vars: Vec<_> = state.scope.iter().collect();
+    vars.sort_by(|a, b| a.0.cmp(b.0));
+    for (var, value) in vars {
+        println!("{} = {}", var, print_conform_value(value));
+    }
+}
+
+pub fn print_pending(state: &ExecutionState) {
+    if state.pending_calls.is_empty() {
+        println!("No pending calls.");
+    }
+    for pc in &state.pending_calls {
+        let status = if pc.handle.is_finished() { "finished" } else { "running" };
+        println!(
+            "{} {} = {}({}) [{}]",
+            pc.id, pc.assignment_var, pc.function, print_conform_value(&pc.argument), status
+        );
+    }
+}
+
+pub fn print_step(step: &Step) {
+    let after: Vec<String> = step.after.iter().map(print_statement).collect();
+    let after = if after.is_empty() { "(removed)".to_string() } else { after.join(" ") };
+    println!("[{}] {}  =>  {}", step.rule, print_statement(&step.before), after);
+}
+
+fn is_incomplete(input: &str) -> bool {
+    let tokens = match tokenize(input) {
+        Ok(tokens) => tokens,
+        Err(QuasarError::ParseError { message, .. }) => return message.starts_with("Unterminated"),
+        Err(_) => return false,
+    };
+    
+    let mut depth = 0i32;
+    for token in &tokens {
+        match token.kind {
+            TokenKind::LeftParen | TokenKind::LeftBrace | TokenKind::LeftBracket => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBrace | TokenKind::RightBracket => depth -= 1,
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        return true;
+    }
+    
+    let kinds: Vec<&TokenKind> = tokens.iter().map(|t| &t.kind).filter(|k| **k != TokenKind::Eof).collect();
+    match kinds.as_slice() {
+        [.., TokenKind::Semicolon] | [TokenKind::Identifier(_)] => false,
+        [.., TokenKind::Identifier(keyword), TokenKind::Identifier(_)] => keyword != "return",
+        _ => true,
+    }
+}
+
+pub async fn read_line(prompt: &str) -> Result<Option<String>> {
+    print!("{}", prompt);
+    std::io::stdout().flush().ok();
+    
+    tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        match std::io::stdin().read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+            Err(e) => Err(QuasarError::RuntimeError(format!("Failed to read input: {}", e))),
+        }
+    })
+    .await
+    .map_err(|e| QuasarError::RuntimeError(format!("Failed to read input: {}", e)))?
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::ast::ConformValue;
+use super::error::{QuasarError, Result};
+use super::external::call_key;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheEntry {
+    pub key: String,
+    pub value: ConformValue,
+    pub expires_at_ms: Option<u64>,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at_ms.is_some_and(|expires_at_ms| now_ms >= expires_at_ms)
+    }
+}
+
+pub trait CacheBackend: Send + Sync + Debug {
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+    fn put(&self, entry: CacheEntry) -> Result<()>;
+    fn remove(&self, key: &str);
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        MemoryCache::default()
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    fn put(&self, entry: CacheEntry) -> Result<()> {
+        self.entries.lock().unwrap().insert(entry.key.clone(), entry);
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+#[derive(Debug)]
+pub struct FileCache {
+    directory: PathBuf,
+}
+
+impl FileCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to create cache directory {}: {}", directory.display(), e))
+        })?;
+        Ok(FileCache { directory })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        self.directory.join(format!("{:016x}.json", hash))
+    }
+}
+
+impl CacheBackend for FileCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let contents = fs::read_to_string(self.path(key)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&contents).ok()?;
+        (entry.key == key).then_some(entry)
+    }
+
+    fn put(&self, entry: CacheEntry) -> Result<()> {
+        let contents = serde_json::to_string(&entry).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to serialize cache entry for {}: {}", entry.key, e))
+        })?;
+        let path = self.path(&entry.key);
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp_path, contents).and_then(|_| fs::rename(&temp_path, &path)).map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            QuasarError::RuntimeError(format!("Failed to write cache entry for {}: {}", entry.key, e))
+        })
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+}
+
+#[derive(Debug)]
+pub struct ResultCache {
+    backend: Arc<dyn CacheBackend>,
+    functions: HashMap<String, Option<Duration>>,
+}
+
+impl ResultCache {
+    pub fn new(backend: Arc<dyn CacheBackend>) -> Self {
+        ResultCache {
+            backend,
+            functions: HashMap::new(),
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        ResultCache::new(Arc::new(MemoryCache::new()))
+    }
+
+    pub fn on_disk(directory: impl Into<PathBuf>) -> Result<Self> {
+        Ok(ResultCache::new(Arc::new(FileCache::new(directory)?)))
+    }
+
+    pub fn enable(mut self, function: &str, ttl: Option<Duration>) -> Self {
+        self.functions.insert(function.to_string(), ttl);
+        self
+    }
+
+    pub fn is_enabled(&self, function: &str) -> bool {
+        self.functions.contains_key(function)
+    }
+
+    pub fn get(&self, function: &str, argument: &ConformValue) -> Option<ConformValue> {
+        if !self.is_enabled(function) {
+            return None;
+        }
+        
+        let key = call_key(function, argument);
+        let entry = self.backend.get(&key)?;
+        if entry.is_expired(unix_now_ms()) {
+            self.backend.remove(&key);
+            return None;
+        }
+        Some(entry.value)
+    }
+
+    pub fn put(&self, function: &str, argument: &ConformValue, value: &ConformValue) -> Result<()> {
+        let Some(ttl) = self.functions.get(function) else {
+            return Ok(());
+        };
+        
+        self.backend.put(CacheEntry {
+            key: call_key(function, argument),
+            value: value.clone(),
+            expires_at_ms: ttl.map(|ttl| unix_now_ms() + ttl.as_millis() as u64),
+        })
+    }
+}
+
+fn unix_now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{PrimitiveValue, Value};
+
+    fn string(text: &str) -> ConformValue {
+        ConformValue::certain(Value::Primitive(PrimitiveValue::String(text.to_string())))
+    }
+
+    fn cache_directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quasar-cache-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn caches_only_enabled_functions() {
+        let cache = ResultCache::in_memory().enable("find", None);
+        cache.put("find", &string("a"), &string("b")).unwrap();
+        cache.put("exists", &string("a"), &string("b")).unwrap();
+        
+        assert_eq!(cache.get("find", &string("a")), Some(string("b")));
+        assert_eq!(cache.get("find", &string("other")), None);
+        assert_eq!(cache.get("exists", &string("a")), None);
+    }
+
+    #[test]
+    fn sub_second_ttl_expires() {
+        let cache = ResultCache::in_memory().enable("find", Some(Duration::from_millis(100)));
+        cache.put("find", &string("a"), &string("b")).unwrap();
+        assert_eq!(cache.get("find", &string("a")), Some(string("b")));
+        
+        std::thread::sleep(Duration::from_millis(150));
+        assert_eq!(cache.get("find", &string("a")), None);
+    }
+
+    #[test]
+    fn file_cache_expires_and_leaves_no_temp_files() {
+        let directory = cache_directory("ttl");
+        let cache = ResultCache::on_disk(&directory).unwrap().enable("find", Some(Duration::from_millis(100)));
+        cache.put("find", &string("a"), &string("b")).unwrap();
+        
+        let files: Vec<_> = fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "json");
+        assert_eq!(cache.get("find", &string("a")), Some(string("b")));
+        
+        std::thread::sleep(Duration::from_millis(150));
+        assert_eq!(cache.get("find", &string("a")), None);
+        let _ = fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn entries_in_seconds_are_misses() {
+        let directory = cache_directory("legacy");
+        let backend = FileCache::new(&directory).unwrap();
+        let key = call_key("find", &string("a"));
+        let legacy = serde_json::json!({ "key": key, "value": string("b"), "expires_at": u64::MAX });
+        fs::write(backend.path(&key), legacy.to_string()).unwrap();
+        
+        assert_eq!(backend.get(&key), None);
+        let _ = fs::remove_dir_all(directory);
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, timeout};
+use crate::ast::ConformValue;
+use super::error::{QuasarError, Result};
+use super::external::ExternalFunction;
+use super::scheduler::Scheduler;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallPolicy {
+    pub timeout: Option<Duration>,
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub backoff_multiplier: f64,
+    pub max_backoff: Duration,
+}
+
+impl Default for CallPolicy {
+    fn default() -> Self {
+        CallPolicy {
+            timeout: None,
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(100),
+            backoff_multiplier: 2.0,
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl CallPolicy {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_backoff(mut self, initial_backoff: Duration, backoff_multiplier: f64) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.backoff_multiplier = backoff_multiplier;
+        self
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self.backoff_multiplier.powi(attempt.saturating_sub(1) as i32);
+        self.initial_backoff.mul_f64(factor).min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolicySpec {
+    pub timeout_ms: Option<u64>,
+    pub max_retries: Option<u32>,
+    pub initial_backoff_ms: Option<u64>,
+    pub backoff_multiplier: Option<f64>,
+    pub max_backoff_ms: Option<u64>,
+}
+
+impl PolicySpec {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn with_backoff(mut self, initial_backoff: Duration, backoff_multiplier: f64) -> Self {
+        self.initial_backoff_ms = Some(initial_backoff.as_millis() as u64);
+        self.backoff_multiplier = Some(backoff_multiplier);
+        self
+    }
+
+    pub fn apply(&self, mut policy: CallPolicy) -> CallPolicy {
+        if let Some(ms) = self.timeout_ms {
+            policy.timeout = Some(Duration::from_millis(ms));
+        }
+        if let Some(retries) = self.max_retries {
+            policy.max_retries = retries;
+        }
+        if let Some(ms) = self.initial_backoff_ms {
+            policy.initial_backoff = Duration::from_millis(ms);
+        }
+        if let Some(multiplier) = self.backoff_multiplier {
+            policy.backoff_multiplier = multiplier;
+        }
+        if let Some(ms) = self.max_backoff_ms {
+            policy.max_backoff = Duration::from_millis(ms);
+        }
+        policy
+    }
+}
+
+pub async fn call_with_policy(
+    func: Arc<dyn ExternalFunction>,
+    argument: &ConformValue,
+    policy: &CallPolicy,
+    scheduler: &Scheduler,
+) -> Result<ConformValue> {
+    let name = func.signature().name;
+    let mut attempt = 0;
+    
+    loop {
+        attempt += 1;
+        
+        let permit = scheduler.acquire(&name).await;
+        let result = match policy.timeout {
+            Some(limit) => match timeout(limit, func.call(argument)).await {
+                Ok(result) => result,
+                Err(_) => Err(QuasarError::Timeout(
+                    format!("{} did not complete within {:?}", name, limit)
+                )),
+            },
+            None => func.call(argument).await,
+        };
+        drop(permit);
+        
+        match result {
+            Err(e) if e.is_transient() && policy.max_retries > 0 => {
+                if attempt > policy.max_retries {
+                    return Err(QuasarError::RetriesExhausted {
+                        function: name,
+                        attempts: attempt,
+                        last_error: Box::new(e),
+                    });
+                }
+                sleep(policy.backoff(attempt)).await;
+            }
+            result => return result,
+        }
+    }
+}
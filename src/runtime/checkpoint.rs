@@ -0,0 +1,171 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::ast::{Program, ConformValue};
+use super::error::{QuasarError, Result};
+use super::registry::FunctionRegistry;
+use super::state::{ExecutionState, CallFailure, RejectedCall, SpeculationStats};
+
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingCallDescriptor {
+    pub id: String,
+    pub assignment_var: String,
+    pub function: String,
+    pub argument: ConformValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub program: Program,
+    pub scope: HashMap<String, ConformValue>,
+    pub call_counter: usize,
+    pub pending_calls: Vec<PendingCallDescriptor>,
+    pub failures: Vec<CallFailure>,
+    pub rejected: Vec<RejectedCall>,
+    pub protected_vars: HashSet<String>,
+    pub completed_calls: HashMap<String, ConformValue>,
+    #[serde(default)]
+    pub speculation: SpeculationStats,
+}
+
+impl Checkpoint {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to serialize checkpoint: {}", e))
+        })?;
+        
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, contents)
+            .and_then(|_| fs::rename(&temp_path, path))
+            .map_err(|e| {
+                QuasarError::RuntimeError(format!("Failed to write checkpoint {}: {}", path.display(), e))
+            })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to read checkpoint {}: {}", path.display(), e))
+        })?;
+        let checkpoint: Checkpoint = serde_json::from_str(&contents).map_err(|e| {
+            QuasarError::RuntimeError(format!("Invalid checkpoint {}: {}", path.display(), e))
+        })?;
+        
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(QuasarError::RuntimeError(format!(
+                "Unsupported checkpoint version {} (expected {})",
+                checkpoint.version, CHECKPOINT_VERSION
+            )));
+        }
+        Ok(checkpoint)
+    }
+}
+
+impl ExecutionState {
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            version: CHECKPOINT_VERSION,
+            program: self.program.clone(),
+            scope: self.scope.clone(),
+            call_counter: self.call_counter,
+            pending_calls: self.pending_calls.iter().filter(|pc| !self.is_speculative(&pc.id)).map(|pc| PendingCallDescriptor {
+                id: pc.id.clone(),
+                assignment_var: pc.assignment_var.clone(),
+                function: pc.function.clone(),
+                argument: pc.argument.clone(),
+            }).collect(),
+            failures: self.failures.clone(),
+            rejected: self.rejected.clone(),
+            protected_vars: self.protected_vars.clone(),
+            completed_calls: self.completed_calls.iter()
+                .filter(|(key, _)| !self.speculative_calls.contains_key(*key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            speculation: SpeculationStats {
+                wasted: self.speculation.wasted + self.speculative_calls.len(),
+                ..self.speculation
+            },
+        }
+    }
+
+    pub fn from_checkpoint(checkpoint: Checkpoint, registry: Arc<FunctionRegistry>) -> Self {
+        let mut state = ExecutionState::with_registry(checkpoint.program, registry);
+        state.scope = checkpoint.scope;
+        state.call_counter = checkpoint.call_counter;
+        state.failures = checkpoint.failures;
+        state.rejected = checkpoint.rejected;
+        state.protected_vars = checkpoint.protected_vars;
+        state.completed_calls = checkpoint.completed_calls;
+        state.speculation = checkpoint.speculation;
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use crate::ast::{PrimitiveValue, Value};
+    use crate::interpreter::{execute_with_inputs, resume};
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::runtime::{CancellationToken, ExecutionOptions, ExecutionStatus};
+    use crate::syntax::parse_program;
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quasar-checkpoint-{}-{}.json", name, std::process::id()))
+    }
+
+    async fn interrupted_state() -> ExecutionState {
+        let slow = TestFunction::echo("echo").with_delay(Duration::from_secs(10));
+        let cancellation = CancellationToken::new();
+        let cancel = cancellation.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        
+        let program = parse_program("a = \"q\"; x = echo(a); return x").unwrap();
+        let options = ExecutionOptions::new(false).with_quiet(true).with_cancellation(cancellation);
+        execute_with_inputs(program, Arc::new(registry(&[&slow])), options, HashMap::new()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn resume_reexecutes_pending_calls() {
+        let state = interrupted_state().await;
+        assert_eq!(state.status, ExecutionStatus::Cancelled);
+        
+        let path = checkpoint_path("resume");
+        state.checkpoint().save(&path).unwrap();
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(checkpoint.pending_calls.len(), 1);
+        assert_eq!(checkpoint.call_counter, state.call_counter);
+        
+        let echo = TestFunction::echo("echo");
+        let options = ExecutionOptions::new(false).with_quiet(true);
+        let resumed = resume(checkpoint, Arc::new(registry(&[&echo])), options).await.unwrap();
+        
+        assert_eq!(resumed.status, ExecutionStatus::Completed);
+        assert_eq!(echo.calls(), 1);
+        let expected = ConformValue::certain(Value::Primitive(PrimitiveValue::String("q".to_string())));
+        assert_eq!(resumed.lookup_var("x"), Some(&expected));
+    }
+
+    #[tokio::test]
+    async fn load_rejects_other_versions() {
+        let mut checkpoint = interrupted_state().await.checkpoint();
+        checkpoint.version = CHECKPOINT_VERSION + 1;
+        let path = checkpoint_path("version");
+        checkpoint.save(&path).unwrap();
+        
+        assert!(Checkpoint::load(&path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use super::call_policy::PolicySpec;
+use super::error::{QuasarError, Result};
+use super::registry::FunctionRegistry;
+use super::scheduler::{RateLimit, Scheduler};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitSpec {
+    pub per_second: f64,
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+impl RateLimitSpec {
+    pub fn to_rate_limit(&self) -> Result<RateLimit> {
+        let limit = RateLimit::per_second(self.per_second)?;
+        Ok(match self.burst {
+            Some(burst) => limit.with_burst(burst),
+            None => limit,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    pub default_policy: Option<PolicySpec>,
+    pub policies: HashMap<String, PolicySpec>,
+    pub max_concurrency: Option<usize>,
+    pub function_concurrency: HashMap<String, usize>,
+    pub rate_limit: Option<RateLimitSpec>,
+    pub rate_limits: HashMap<String, RateLimitSpec>,
+    pub latency_ms: HashMap<String, u64>,
+}
+
+impl RuntimeConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to read config {}: {}", path.display(), e))
+        })?;
+        serde_json::from_str(&text).map_err(|e| {
+            QuasarError::RuntimeError(format!("Invalid config {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn apply(&self, registry: &mut FunctionRegistry) -> Result<()> {
+        if let Some(spec) = &self.default_policy {
+            let policy = spec.apply(registry.default_policy());
+            registry.set_default_policy(policy);
+        }
+        for (function, spec) in &self.policies {
+            registry.set_policy(function, spec.clone());
+        }
+        for (function, ms) in &self.latency_ms {
+            registry.set_latency_estimate(function, Duration::from_millis(*ms));
+        }
+        
+        let schedules = self.max_concurrency.is_some()
+            || !self.function_concurrency.is_empty()
+            || self.rate_limit.is_some()
+            || !self.rate_limits.is_empty();
+        if !schedules {
+            return Ok(());
+        }
+        
+        let mut scheduler = Scheduler::new();
+        if let Some(limit) = self.max_concurrency {
+            scheduler = scheduler.with_max_concurrency(limit)?;
+        }
+        for (function, limit) in &self.function_concurrency {
+            scheduler = scheduler.with_function_concurrency(function, *limit)?;
+        }
+        if let Some(spec) = &self.rate_limit {
+            scheduler = scheduler.with_rate_limit(spec.to_rate_limit()?);
+        }
+        for (function, spec) in &self.rate_limits {
+            scheduler = scheduler.with_function_rate_limit(function, spec.to_rate_limit()?);
+        }
+        registry.set_scheduler(scheduler);
+        Ok(())
+    }
+}
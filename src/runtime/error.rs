@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum QuasarError {
     #[error("Variable not found: {0}")]
     VariableNotFound(String),
@@ -16,6 +17,35 @@ pub enum QuasarError {
     
     #[error("Runtime error: {0}")]
     RuntimeError(String),
+    
+    #[error("Timeout: {0}")]
+    Timeout(String),
+    
+    #[error("Input error: {0}")]
+    InputError(String),
+    
+    #[error("Parse error at {line}:{column}: {message}")]
+    ParseError {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    
+    #[error("Replay diverged: {0}")]
+    ReplayDivergence(String),
+    
+    #[error("{function} failed after {attempts} attempts: {last_error}")]
+    RetriesExhausted {
+        function: String,
+        attempts: u32,
+        last_error: Box<QuasarError>,
+    },
+}
+
+impl QuasarError {
+    pub fn is_transient(&self) -> bool {
+        matches!(self, QuasarError::ExternalFunctionError(_) | QuasarError::Timeout(_))
+    }
 }
 
 pub type Result<T> = std::result::Result<T, QuasarError>;
\ No newline at end of file
@@ -1,20 +1,43 @@
 use async_trait::async_trait;
-use crate::ast::{Value, ConformValue};
+use crate::ast::{Value, ConformValue, ValueType};
 use super::error::Result;
+use super::signature::{FunctionSignature, Effects};
 use std::time::Duration;
 use tokio::time::sleep;
 
 #[async_trait]
 pub trait ExternalFunction: Send + Sync {
+    fn signature(&self) -> FunctionSignature;
+
     async fn call(&self, args: &ConformValue) -> Result<ConformValue>;
 }
 
+tokio::task_local! {
+    pub static CURRENT_CALL_ID: String;
+}
+
+pub fn call_key(function: &str, argument: &ConformValue) -> String {
+    let argument = serde_json::to_string(argument).unwrap_or_else(|_| format!("{:?}", argument));
+    format!("{}:{}", function, argument)
+}
+
+fn pure_effects() -> Effects {
+    Effects { pure: true, idempotent: true }
+}
+
 pub struct FindFunction;
 
 #[async_trait]
 impl ExternalFunction for FindFunction {
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::new("find", ValueType::String, ValueType::List(Box::new(ValueType::String)))
+            .with_description("Find all patches of the image that contain the given object")
+            .with_effects(pure_effects())
+            .with_latency(Duration::from_secs(1))
+    }
+
     async fn call(&self, _args: &ConformValue) -> Result<ConformValue> {
-        println!("External: Calling find() function...");
+        eprintln!("External: Calling find() function...");
         sleep(Duration::from_secs(1)).await;
         
         let patches = vec![
@@ -30,12 +53,19 @@ pub struct SimpleQueryFunction;
 
 #[async_trait]
 impl ExternalFunction for SimpleQueryFunction {
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::new("simple_query", ValueType::String, ValueType::Boolean)
+            .with_description("Ask a simple yes/no question about the given patch")
+            .with_effects(pure_effects())
+            .with_latency(Duration::from_millis(500))
+    }
+
     async fn call(&self, _args: &ConformValue) -> Result<ConformValue> {
-        println!("External: Calling simple_query() function...");
+        eprintln!("External: Calling simple_query() function...");
         sleep(Duration::from_millis(500)).await;
         
         Ok(ConformValue::certain(Value::Primitive(
-            crate::ast::PrimitiveValue::String("yes".to_string())
+            crate::ast::PrimitiveValue::Boolean(true)
         )))
     }
 }
@@ -44,8 +74,15 @@ pub struct ExistsFunction;
 
 #[async_trait]
 impl ExternalFunction for ExistsFunction {
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::new("exists", ValueType::String, ValueType::Boolean)
+            .with_description("Check whether the given object exists in the patch")
+            .with_effects(pure_effects())
+            .with_latency(Duration::from_millis(300))
+    }
+
     async fn call(&self, _args: &ConformValue) -> Result<ConformValue> {
-        println!("External: Calling exists() function...");
+        eprintln!("External: Calling exists() function...");
         sleep(Duration::from_millis(300)).await;
         
         Ok(ConformValue::certain(Value::Primitive(
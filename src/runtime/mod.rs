@@ -1,7 +1,31 @@
 pub mod error;
 pub mod state;
 pub mod external;
+pub mod signature;
+pub mod registry;
+pub mod call_policy;
+pub mod scheduler;
+pub mod cancellation;
+pub mod options;
+pub mod cache;
+pub mod checkpoint;
+pub mod replay;
+pub mod trace;
+pub mod config;
+#[cfg(test)]
+pub mod testing;
 
 pub use error::*;
 pub use state::*;
-pub use external::*;
\ No newline at end of file
+pub use external::*;
+pub use signature::*;
+pub use registry::*;
+pub use call_policy::*;
+pub use scheduler::*;
+pub use cancellation::*;
+pub use options::*;
+pub use cache::*;
+pub use checkpoint::*;
+pub use replay::*;
+pub use trace::*;
+pub use config::*;
\ No newline at end of file
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use super::cancellation::CancellationToken;
+use super::cache::ResultCache;
+use super::replay::Recorder;
+use super::trace::Trace;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailureMode {
+    #[default]
+    Abort,
+    BindError,
+    BindUnknown,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOptions {
+    pub with_approval: bool,
+    pub cancellation: CancellationToken,
+    pub failure_mode: FailureMode,
+    pub cache: Option<Arc<ResultCache>>,
+    pub checkpoint_path: Option<PathBuf>,
+    pub recorder: Option<Arc<Recorder>>,
+    pub trace: Option<Arc<Trace>>,
+    pub quiet: bool,
+    pub fan_out_limit: Option<usize>,
+    pub speculate: bool,
+}
+
+impl ExecutionOptions {
+    pub fn new(with_approval: bool) -> Self {
+        ExecutionOptions {
+            with_approval,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn with_failure_mode(mut self, failure_mode: FailureMode) -> Self {
+        self.failure_mode = failure_mode;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: Arc<ResultCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn with_checkpoint_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint_path = Some(path.into());
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: Arc<Recorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn with_trace(mut self, trace: Arc<Trace>) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    pub fn with_fan_out(mut self, limit: usize) -> Self {
+        self.fan_out_limit = Some(limit);
+        self
+    }
+
+    pub fn with_speculation(mut self, speculate: bool) -> Self {
+        self.speculate = speculate;
+        self
+    }
+}
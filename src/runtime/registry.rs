@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use super::external::{ExternalFunction, FindFunction, SimpleQueryFunction, ExistsFunction};
+use super::signature::FunctionSignature;
+use super::call_policy::{CallPolicy, PolicySpec};
+use super::scheduler::Scheduler;
+use super::replay::ReplayRegistry;
+
+#[derive(Clone, Default)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, Arc<dyn ExternalFunction>>,
+    policies: HashMap<String, PolicySpec>,
+    latencies: HashMap<String, Duration>,
+    default_policy: CallPolicy,
+    scheduler: Arc<Scheduler>,
+    replay: Option<Arc<ReplayRegistry>>,
+}
+
+impl FunctionRegistry {
+    pub fn new() -> Self {
+        FunctionRegistry::default()
+    }
+
+    pub fn with_builtins() -> Self {
+        let mut registry = FunctionRegistry::new();
+        registry.register(FindFunction);
+        registry.register(SimpleQueryFunction);
+        registry.register(ExistsFunction);
+        registry
+    }
+
+    pub fn register(&mut self, function: impl ExternalFunction + 'static) {
+        let name = function.signature().name;
+        self.functions.insert(name, Arc::new(function));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn ExternalFunction>> {
+        self.functions.get(name).cloned()
+    }
+
+    pub fn set_default_policy(&mut self, policy: CallPolicy) {
+        self.default_policy = policy;
+    }
+
+    pub fn default_policy(&self) -> CallPolicy {
+        self.default_policy.clone()
+    }
+
+    pub fn set_policy(&mut self, name: &str, policy: PolicySpec) {
+        self.policies.insert(name.to_string(), policy);
+    }
+
+    pub fn policy(&self, name: &str) -> CallPolicy {
+        match self.policies.get(name) {
+            Some(spec) => spec.apply(self.default_policy.clone()),
+            None => self.default_policy.clone(),
+        }
+    }
+
+    pub fn set_latency_estimate(&mut self, name: &str, latency: Duration) {
+        self.latencies.insert(name.to_string(), latency);
+    }
+
+    pub fn latency_estimate(&self, name: &str) -> Option<Duration> {
+        self.latencies.get(name).copied().or_else(|| self.signature(name)?.latency)
+    }
+
+    pub fn set_scheduler(&mut self, scheduler: Scheduler) {
+        self.scheduler = Arc::new(scheduler);
+    }
+
+    pub fn scheduler(&self) -> Arc<Scheduler> {
+        self.scheduler.clone()
+    }
+
+    pub fn set_replay(&mut self, replay: Arc<ReplayRegistry>) {
+        self.replay = Some(replay);
+    }
+
+    pub fn replay(&self) -> Option<Arc<ReplayRegistry>> {
+        self.replay.clone()
+    }
+
+    pub fn signature(&self, name: &str) -> Option<FunctionSignature> {
+        self.functions.get(name).map(|f| f.signature())
+    }
+
+    pub fn signatures(&self) -> Vec<FunctionSignature> {
+        let mut signatures: Vec<_> = self.functions.values().map(|f| f.signature()).collect();
+        signatures.sort_by(|a, b| a.name.cmp(&b.name));
+        signatures
+    }
+
+    pub fn to_json_schema(&self) -> serde_json::Value {
+        serde_json::Value::Array(self.signatures().iter().map(|s| s.to_json_schema()).collect())
+    }
+
+    pub fn prompt_summary(&self) -> String {
+        let mut summary = String::from("## QUASAR grammar\n\n");
+        summary.push_str(GRAMMAR);
+        summary.push_str("\n## Available external functions\n\n");
+        for signature in self.signatures() {
+            summary.push_str(&format!("- {}\n", signature.summary()));
+        }
+        summary
+    }
+}
+
+pub const GRAMMAR: &str = "\
+program ::= stmt ... stmt return x
+stmt    ::= x = op;
+op      ::= literal                        constant: true, 42, 1.5, \"text\", null, [literal, ...]
+          | y                              alias of another variable
+          | (y1, ..., yn)                  tuple of variables
+          | f(y)                           call external function f with argument y
+          | proj i y                       i-th element (0-based) of tuple y
+          | fold xs init {p => program}    fold over list xs starting from init; p is bound to (acc, element)
+          | if c {program} else {program}  branch on boolean c
+          | join {y1, ..., yn}             union of the possible values of y1, ..., yn
+          | try {program} else {e => program}
+                                           run program; if it fails, bind the error message to e and run the handler
+Every argument of an operation must be a variable; bind literals to variables first.
+External calls run in parallel as soon as their argument is available.
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::call_policy::PolicySpec;
+
+    #[test]
+    fn function_policy_overrides_only_the_fields_it_sets() {
+        let mut registry = FunctionRegistry::new();
+        registry.set_default_policy(CallPolicy::default().with_timeout(Duration::from_secs(5)).with_retries(1));
+        registry.set_policy("find", PolicySpec::default().with_retries(3));
+        
+        let policy = registry.policy("find");
+        assert_eq!(policy.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(policy.max_retries, 3);
+        assert_eq!(registry.policy("exists"), registry.default_policy());
+    }
+}
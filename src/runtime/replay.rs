@@ -0,0 +1,308 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::ast::ConformValue;
+use super::error::{QuasarError, Result};
+use super::external::{ExternalFunction, call_key, CURRENT_CALL_ID};
+use super::registry::FunctionRegistry;
+use super::signature::FunctionSignature;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecordedOutcome {
+    Ok(ConformValue),
+    Err(QuasarError),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallRecord {
+    pub sequence: usize,
+    pub call_id: String,
+    pub function: String,
+    pub argument: ConformValue,
+    pub outcome: RecordedOutcome,
+}
+
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<File>,
+    records: Mutex<Vec<CallRecord>>,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to create recording {}: {}", path.display(), e))
+        })?;
+        Ok(Recorder {
+            file: Mutex::new(file),
+            records: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn record(
+        &self,
+        call_id: &str,
+        function: &str,
+        argument: &ConformValue,
+        outcome: &Result<ConformValue>,
+    ) -> Result<()> {
+        let mut records = self.records.lock().unwrap();
+        let record = CallRecord {
+            sequence: records.len(),
+            call_id: call_id.to_string(),
+            function: function.to_string(),
+            argument: argument.clone(),
+            outcome: match outcome {
+                Ok(value) => RecordedOutcome::Ok(value.clone()),
+                Err(e) => RecordedOutcome::Err(e.clone()),
+            },
+        };
+        
+        let line = serde_json::to_string(&record).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to serialize call record: {}", e))
+        })?;
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line).and_then(|_| file.flush()).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to write call record: {}", e))
+        })?;
+        
+        records.push(record);
+        Ok(())
+    }
+
+    pub fn records(&self) -> Vec<CallRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayRegistry {
+    records: Mutex<HashMap<String, VecDeque<CallRecord>>>,
+}
+
+impl ReplayRegistry {
+    pub fn load(path: impl AsRef<Path>) -> Result<Arc<Self>> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to read recording {}: {}", path.display(), e))
+        })?;
+        
+        let mut records = Vec::new();
+        for (line_number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(line).map_err(|e| {
+                QuasarError::RuntimeError(format!("Invalid call record at {}:{}: {}", path.display(), line_number + 1, e))
+            })?;
+            records.push(record);
+        }
+        Ok(Self::from_records(records))
+    }
+
+    pub fn from_records(mut records: Vec<CallRecord>) -> Arc<Self> {
+        records.sort_by_key(|r| r.sequence);
+        let mut by_key: HashMap<String, VecDeque<CallRecord>> = HashMap::new();
+        for record in records {
+            by_key.entry(call_key(&record.function, &record.argument)).or_default().push_back(record);
+        }
+        Arc::new(ReplayRegistry { records: Mutex::new(by_key) })
+    }
+
+    pub fn registry(self: &Arc<Self>, functions: &FunctionRegistry) -> FunctionRegistry {
+        let mut registry = FunctionRegistry::new();
+        registry.set_replay(self.clone());
+        for signature in functions.signatures() {
+            registry.register(ReplayFunction {
+                signature,
+                replay: self.clone(),
+            });
+        }
+        registry
+    }
+
+    pub fn next(&self, function: &str, argument: &ConformValue) -> Result<ConformValue> {
+        let call_id = CURRENT_CALL_ID.try_with(|id| id.clone()).unwrap_or_default();
+        let record = self.records.lock().unwrap()
+            .get_mut(&call_key(function, argument))
+            .and_then(|queue| {
+                let position = queue.iter().position(|r| r.call_id == call_id)?;
+                queue.remove(position)
+            })
+            .ok_or_else(|| QuasarError::ReplayDivergence(format!(
+                "no recorded call {} {}({:?})", call_id, function, argument
+            )))?;
+        
+        match record.outcome {
+            RecordedOutcome::Ok(value) => Ok(value),
+            RecordedOutcome::Err(error) => Err(error),
+        }
+    }
+
+    pub fn unconsumed(&self) -> Vec<CallRecord> {
+        let mut remaining: Vec<_> = self.records.lock().unwrap().values().flatten().cloned().collect();
+        remaining.sort_by_key(|r| r.sequence);
+        remaining
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        match self.unconsumed().first() {
+            None => Ok(()),
+            Some(record) => Err(QuasarError::ReplayDivergence(format!(
+                "recorded call #{} {}({:?}) was never made",
+                record.sequence, record.function, record.argument
+            ))),
+        }
+    }
+}
+
+struct ReplayFunction {
+    signature: FunctionSignature,
+    replay: Arc<ReplayRegistry>,
+}
+
+#[async_trait]
+impl ExternalFunction for ReplayFunction {
+    fn signature(&self) -> FunctionSignature {
+        self.signature.clone()
+    }
+
+    async fn call(&self, args: &ConformValue) -> Result<ConformValue> {
+        self.replay.next(&self.signature.name, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::ast::{PrimitiveValue, Value, ValueType};
+    use crate::interpreter::execute_with_inputs;
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::runtime::{ExecutionOptions, ExecutionState, ResultCache};
+    use crate::syntax::parse_program;
+
+    const PROGRAM: &str = "a = \"q\"; x = echo(a); y = try { r = broken(a); return r } else { e => return e }; t = (x, y); return t";
+
+    fn recording_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quasar-replay-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    async fn execute(registry: FunctionRegistry, options: ExecutionOptions) -> Result<ExecutionState> {
+        execute_with_inputs(parse_program(PROGRAM).unwrap(), Arc::new(registry), options.with_quiet(true), HashMap::new()).await
+    }
+
+    #[tokio::test]
+    async fn replay_reproduces_recorded_run() {
+        let echo = TestFunction::echo("echo");
+        let broken = TestFunction::failing("broken", ValueType::Any);
+        let functions = registry(&[&echo, &broken]);
+        let path = recording_path("reproduce");
+        let recorder = Arc::new(Recorder::create(&path).unwrap());
+        
+        let recorded = execute(functions.clone(), ExecutionOptions::new(false).with_recorder(recorder.clone())).await.unwrap();
+        let replay = ReplayRegistry::load(&path).unwrap();
+        let replayed = execute(replay.registry(&functions), ExecutionOptions::new(false)).await.unwrap();
+        
+        assert_eq!(recorder.records().len(), 2);
+        assert_eq!(replayed.lookup_var("t"), recorded.lookup_var("t"));
+        assert_eq!(echo.calls(), 1);
+        assert_eq!(broken.calls(), 1);
+        assert!(replay.unconsumed().is_empty());
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn replayed_errors_keep_their_variant() {
+        let path = recording_path("variant");
+        let recorder = Recorder::create(&path).unwrap();
+        let argument = ConformValue::certain(Value::Primitive(PrimitiveValue::String("q".to_string())));
+        let error = QuasarError::Timeout("broken did not complete".to_string());
+        recorder.record("?S1", "broken", &argument, &Err(error.clone())).unwrap();
+        
+        let replay = ReplayRegistry::load(&path).unwrap();
+        let replayed = CURRENT_CALL_ID.scope("?S1".to_string(), async { replay.next("broken", &argument) }).await;
+        assert_eq!(replayed, Err(error));
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn records_cache_hits() {
+        let echo = TestFunction::echo("echo");
+        let broken = TestFunction::failing("broken", ValueType::Any);
+        let functions = registry(&[&echo, &broken]);
+        let cache = Arc::new(ResultCache::in_memory().enable("echo", None));
+        execute(functions.clone(), ExecutionOptions::new(false).with_cache(cache.clone())).await.unwrap();
+        
+        let path = recording_path("cache");
+        let recorder = Arc::new(Recorder::create(&path).unwrap());
+        let options = ExecutionOptions::new(false).with_cache(cache.clone()).with_recorder(recorder.clone());
+        execute(functions.clone(), options).await.unwrap();
+        
+        assert_eq!(echo.calls(), 1);
+        assert!(recorder.records().iter().any(|record| record.function == "echo"));
+        
+        let replay = ReplayRegistry::load(&path).unwrap();
+        execute(replay.registry(&functions), ExecutionOptions::new(false).with_cache(cache)).await.unwrap();
+        assert!(replay.unconsumed().is_empty());
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn verifies_unconsumed_records_when_execution_finishes() {
+        let echo = TestFunction::echo("echo");
+        let broken = TestFunction::failing("broken", ValueType::Any);
+        let functions = registry(&[&echo, &broken]);
+        let recorder = Arc::new(Recorder::create(recording_path("verify")).unwrap());
+        execute(functions.clone(), ExecutionOptions::new(false).with_recorder(recorder.clone())).await.unwrap();
+        
+        let mut records = recorder.records();
+        let mut extra = records[0].clone();
+        extra.sequence = records.len();
+        extra.argument = ConformValue::certain(Value::Primitive(PrimitiveValue::String("never".to_string())));
+        records.push(extra);
+        
+        let replay = ReplayRegistry::from_records(records);
+        let result = execute(replay.registry(&functions), ExecutionOptions::new(false)).await;
+        assert!(matches!(result, Err(QuasarError::ReplayDivergence(_))));
+        let _ = fs::remove_file(recording_path("verify"));
+    }
+
+    #[tokio::test]
+    async fn changed_program_diverges() {
+        let echo = TestFunction::echo("echo");
+        let broken = TestFunction::failing("broken", ValueType::Any);
+        let functions = registry(&[&echo, &broken]);
+        let recorder = Arc::new(Recorder::create(recording_path("changed")).unwrap());
+        execute(functions.clone(), ExecutionOptions::new(false).with_recorder(recorder.clone())).await.unwrap();
+        let _ = fs::remove_file(recording_path("changed"));
+        
+        let changed = "a = \"other\"; x = echo(a); return x";
+        let replay = ReplayRegistry::from_records(recorder.records());
+        let options = ExecutionOptions::new(false).with_quiet(true);
+        let result = execute_with_inputs(parse_program(changed).unwrap(), Arc::new(replay.registry(&functions)), options, HashMap::new()).await;
+        assert!(matches!(result, Err(QuasarError::ReplayDivergence(_))));
+    }
+
+    #[tokio::test]
+    async fn reordered_calls_diverge() {
+        let echo = TestFunction::echo("echo");
+        let functions = registry(&[&echo]);
+        let recorded = "a = \"a\"; b = \"b\"; x = echo(a); y = echo(b); t = (x, y); return t";
+        let reordered = "a = \"a\"; b = \"b\"; y = echo(b); x = echo(a); t = (x, y); return t";
+        let recorder = Arc::new(Recorder::create(recording_path("reordered")).unwrap());
+        let options = ExecutionOptions::new(false).with_quiet(true).with_recorder(recorder.clone());
+        execute_with_inputs(parse_program(recorded).unwrap(), Arc::new(functions.clone()), options, HashMap::new()).await.unwrap();
+        let _ = fs::remove_file(recording_path("reordered"));
+        
+        let replay = ReplayRegistry::from_records(recorder.records());
+        let options = ExecutionOptions::new(false).with_quiet(true);
+        let result = execute_with_inputs(parse_program(reordered).unwrap(), Arc::new(replay.registry(&functions)), options, HashMap::new()).await;
+        assert!(matches!(result, Err(QuasarError::ReplayDivergence(_))));
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::sleep;
+use super::error::{QuasarError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    pub fn per_second(per_second: f64) -> Result<Self> {
+        if !per_second.is_finite() || per_second <= 0.0 {
+            return Err(QuasarError::InvalidOperation(format!(
+                "Rate limit must be a positive number of calls per second, got {}", per_second
+            )));
+        }
+        Ok(RateLimit { capacity: per_second.ceil().max(1.0) as u32, per_second })
+    }
+
+    pub fn with_burst(mut self, capacity: u32) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        TokenBucket {
+            limit,
+            state: Mutex::new((limit.capacity as f64, Instant::now())),
+        }
+    }
+
+    async fn take(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let refill = now.duration_since(state.1).as_secs_f64() * self.limit.per_second;
+                state.0 = (state.0 + refill).min(self.limit.capacity as f64);
+                state.1 = now;
+                
+                if state.0 >= 1.0 {
+                    state.0 -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - state.0) / self.limit.per_second)
+            };
+            sleep(wait).await;
+        }
+    }
+}
+
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl<'a> QueuedGuard<'a> {
+    fn enter(queued: &'a AtomicUsize) -> Self {
+        queued.fetch_add(1, Ordering::SeqCst);
+        QueuedGuard(queued)
+    }
+}
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub struct SchedulerPermit {
+    _function: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+}
+
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    global_limit: Option<Arc<Semaphore>>,
+    function_limits: HashMap<String, Arc<Semaphore>>,
+    global_rate: Option<TokenBucket>,
+    function_rates: HashMap<String, TokenBucket>,
+    queued: AtomicUsize,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler::default()
+    }
+
+    pub fn with_max_concurrency(mut self, limit: usize) -> Result<Self> {
+        self.global_limit = Some(semaphore("Concurrency limit", limit)?);
+        Ok(self)
+    }
+
+    pub fn with_function_concurrency(mut self, function: &str, limit: usize) -> Result<Self> {
+        let semaphore = semaphore(&format!("Concurrency limit for {}", function), limit)?;
+        self.function_limits.insert(function.to_string(), semaphore);
+        Ok(self)
+    }
+
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.global_rate = Some(TokenBucket::new(limit));
+        self
+    }
+
+    pub fn with_function_rate_limit(mut self, function: &str, limit: RateLimit) -> Self {
+        self.function_rates.insert(function.to_string(), TokenBucket::new(limit));
+        self
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    pub async fn acquire(&self, function: &str) -> SchedulerPermit {
+        let queued = QueuedGuard::enter(&self.queued);
+        
+        let function_permit = match self.function_limits.get(function) {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        let global_permit = match &self.global_limit {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        
+        if let Some(bucket) = self.function_rates.get(function) {
+            bucket.take().await;
+        }
+        if let Some(bucket) = &self.global_rate {
+            bucket.take().await;
+        }
+        
+        drop(queued);
+        
+        SchedulerPermit {
+            _function: function_permit,
+            _global: global_permit,
+        }
+    }
+}
+
+fn semaphore(name: &str, limit: usize) -> Result<Arc<Semaphore>> {
+    if limit == 0 {
+        return Err(QuasarError::InvalidOperation(format!("{} must be at least 1", name)));
+    }
+    Ok(Arc::new(Semaphore::new(limit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    #[test]
+    fn rejects_zero_limits() {
+        assert!(Scheduler::new().with_max_concurrency(0).is_err());
+        assert!(Scheduler::new().with_function_concurrency("find", 0).is_err());
+        assert!(RateLimit::per_second(0.0).is_err());
+        assert!(RateLimit::per_second(-1.0).is_err());
+        assert!(RateLimit::per_second(f64::NAN).is_err());
+        assert!(RateLimit::per_second(f64::INFINITY).is_err());
+    }
+
+    #[tokio::test]
+    async fn limits_concurrency_per_function() {
+        let scheduler = Scheduler::new().with_function_concurrency("find", 1).unwrap();
+        let permit = scheduler.acquire("find").await;
+        
+        assert!(timeout(Duration::from_millis(50), scheduler.acquire("find")).await.is_err());
+        assert!(timeout(Duration::from_millis(50), scheduler.acquire("exists")).await.is_ok());
+        
+        drop(permit);
+        assert!(timeout(Duration::from_millis(50), scheduler.acquire("find")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn queued_count_drops_when_acquire_is_aborted() {
+        let scheduler = Arc::new(Scheduler::new().with_max_concurrency(1).unwrap());
+        let permit = scheduler.acquire("find").await;
+        
+        let waiting = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move {
+                scheduler.acquire("find").await;
+            })
+        };
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(scheduler.queued(), 1);
+        
+        waiting.abort();
+        let _ = waiting.await;
+        assert_eq!(scheduler.queued(), 0);
+        drop(permit);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_spaces_out_calls() {
+        let scheduler = Scheduler::new().with_rate_limit(RateLimit::per_second(20.0).unwrap().with_burst(1));
+        let start = Instant::now();
+        for _ in 0..3 {
+            scheduler.acquire("find").await;
+        }
+        
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,102 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::ast::{ConformValue, ValueType};
+use super::error::{QuasarError, Result};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Effects {
+    pub pure: bool,
+    pub idempotent: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSignature {
+    pub name: String,
+    pub parameter: ValueType,
+    pub return_type: ValueType,
+    pub description: String,
+    pub effects: Effects,
+    #[serde(default)]
+    pub latency: Option<Duration>,
+}
+
+impl FunctionSignature {
+    pub fn new(name: &str, parameter: ValueType, return_type: ValueType) -> Self {
+        FunctionSignature {
+            name: name.to_string(),
+            parameter,
+            return_type,
+            description: String::new(),
+            effects: Effects::default(),
+            latency: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    pub fn with_effects(mut self, effects: Effects) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    pub fn to_json_schema(&self) -> serde_json::Value {
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "argument": self.parameter.json_schema(),
+                },
+                "required": ["argument"],
+            },
+            "returns": self.return_type.json_schema(),
+            "effects": {
+                "pure": self.effects.pure,
+                "idempotent": self.effects.idempotent,
+            },
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        let mut line = format!("{}({}) -> {}", self.name, self.parameter, self.return_type);
+        if !self.description.is_empty() {
+            line.push_str(&format!(": {}", self.description));
+        }
+        if self.effects.pure {
+            line.push_str(" [pure]");
+        }
+        line
+    }
+
+    pub fn check_argument(&self, argument: &ConformValue) -> Result<()> {
+        if self.parameter.accepts_conform(argument) {
+            Ok(())
+        } else {
+            Err(QuasarError::TypeError(format!(
+                "{} expects an argument of type {}, got {:?}",
+                self.name, self.parameter, argument
+            )))
+        }
+    }
+
+    pub fn check_return(&self, result: &ConformValue) -> Result<()> {
+        if self.return_type.accepts_conform(result) {
+            Ok(())
+        } else {
+            Err(QuasarError::TypeError(format!(
+                "{} should return {}, got {:?}",
+                self.name, self.return_type, result
+            )))
+        }
+    }
+}
@@ -1,28 +1,260 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
 use tokio::task::JoinHandle;
-use crate::ast::{Program, ConformValue};
-use super::error::Result;
+use crate::ast::{Program, ConformValue, Statement, Value, ValueType};
+use super::error::{QuasarError, Result};
+use super::cache::ResultCache;
+use super::registry::FunctionRegistry;
+use super::options::{ExecutionOptions, FailureMode};
+use super::trace::Rule;
 
 pub struct PendingCall {
     pub id: String,
     pub assignment_var: String,
+    pub function: String,
+    pub argument: ConformValue,
     pub handle: JoinHandle<Result<ConformValue>>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedCall {
+    pub assignment_var: String,
+    pub function: String,
+    pub argument: ConformValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallFailure {
+    pub assignment_var: String,
+    pub function: String,
+    pub argument: ConformValue,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeculativeCall {
+    pub id: String,
+    pub if_var: String,
+    pub then_branch: bool,
+    pub else_branch: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeculationStats {
+    pub dispatched: usize,
+    pub used: usize,
+    pub wasted: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    Running,
+    Completed,
+    Stuck,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub status: ExecutionStatus,
+    pub return_var: String,
+    pub return_value: Option<ConformValue>,
+    pub scope: BTreeMap<String, ConformValue>,
+    pub failures: Vec<CallFailure>,
+    pub rejected: Vec<RejectedCall>,
+    #[serde(default)]
+    pub speculation: SpeculationStats,
+}
+
+pub fn inputs_from_json(
+    program: &Program,
+    json: &serde_json::Map<String, serde_json::Value>,
+) -> Result<HashMap<String, Value>> {
+    let mut inputs = HashMap::new();
+    for (name, json) in json {
+        let value_type = program.inputs.iter().find(|input| input.name == *name)
+            .map_or(ValueType::Any, |input| input.value_type.clone());
+        let value = value_type.value_from_json(json).ok_or_else(|| {
+            QuasarError::TypeError(format!("Input {} expects {}, got {}", name, value_type, json))
+        })?;
+        inputs.insert(name.clone(), value);
+    }
+    Ok(inputs)
+}
+
 pub struct ExecutionState {
     pub program: Program,
     pub pending_calls: Vec<PendingCall>,
     pub scope: HashMap<String, ConformValue>,
     pub call_counter: usize,
+    pub registry: Arc<FunctionRegistry>,
+    pub options: ExecutionOptions,
+    pub status: ExecutionStatus,
+    pub failures: Vec<CallFailure>,
+    pub rejected: Vec<RejectedCall>,
+    pub protected_vars: HashSet<String>,
+    pub inflight_calls: HashMap<String, String>,
+    pub completed_calls: HashMap<String, ConformValue>,
+    pub speculative_calls: HashMap<String, SpeculativeCall>,
+    pub speculation: SpeculationStats,
+    pub binding_log: Option<Vec<String>>,
 }
 
 impl ExecutionState {
     pub fn new(program: Program) -> Self {
+        Self::with_registry(program, Arc::new(FunctionRegistry::with_builtins()))
+    }
+    
+    pub fn with_registry(program: Program, registry: Arc<FunctionRegistry>) -> Self {
         ExecutionState {
             program,
             pending_calls: Vec::new(),
             scope: HashMap::new(),
             call_counter: 0,
+            registry,
+            options: ExecutionOptions::default(),
+            status: ExecutionStatus::Running,
+            failures: Vec::new(),
+            rejected: Vec::new(),
+            protected_vars: HashSet::new(),
+            inflight_calls: HashMap::new(),
+            completed_calls: HashMap::new(),
+            speculative_calls: HashMap::new(),
+            speculation: SpeculationStats::default(),
+            binding_log: None,
+        }
+    }
+    
+    pub fn with_options(mut self, options: ExecutionOptions) -> Self {
+        self.options = options;
+        self
+    }
+    
+    pub fn result_cache(&self) -> Option<Arc<ResultCache>> {
+        self.options.cache.clone().filter(|_| self.registry.replay().is_none())
+    }
+    
+    pub fn is_cancelled(&self) -> bool {
+        self.options.cancellation.is_cancelled()
+    }
+    
+    pub fn protect_var(&mut self, name: String) {
+        self.protected_vars.insert(name);
+    }
+    
+    pub fn is_protected(&self, name: &str) -> bool {
+        self.protected_vars.contains(name)
+    }
+    
+    pub fn recover(&self, name: &str, error: QuasarError) -> Result<ConformValue> {
+        if self.options.failure_mode == FailureMode::Abort && !self.is_protected(name) {
+            return Err(error);
+        }
+        Ok(ConformValue::certain(Value::Error(error.to_string())))
+    }
+    
+    pub fn unknown_result(&self, function: &str) -> Result<ConformValue> {
+        let return_type = self.registry.signature(function).map_or(ValueType::Any, |signature| signature.return_type);
+        ConformValue::unknown(&return_type).ok_or_else(|| QuasarError::InvalidOperation(format!(
+            "FailureMode::BindUnknown cannot represent an unknown {} returned by {}; use BindError instead",
+            return_type, function
+        )))
+    }
+    
+    pub fn handle_call_failure(
+        &mut self,
+        assignment_vars: &[String],
+        function: &str,
+        argument: &ConformValue,
+        error: QuasarError,
+    ) -> Result<ConformValue> {
+        if let QuasarError::ReplayDivergence(_) = error {
+            return Err(error);
+        }
+        
+        let value = match self.options.failure_mode {
+            FailureMode::Abort if assignment_vars.iter().any(|var| !self.is_protected(var)) => return Err(error),
+            FailureMode::Abort => None,
+            FailureMode::BindError => None,
+            FailureMode::BindUnknown => Some(self.unknown_result(function)?),
+        };
+        
+        let message = error.to_string();
+        for assignment_var in assignment_vars {
+            self.log(&format!("External: {}({}) failed: {}", function, assignment_var, error));
+            self.failures.push(CallFailure {
+                assignment_var: assignment_var.clone(),
+                function: function.to_string(),
+                argument: argument.clone(),
+                error: message.clone(),
+            });
+        }
+        
+        Ok(value.unwrap_or_else(|| ConformValue::certain(Value::Error(message))))
+    }
+    
+    pub fn abort_pending_calls(&mut self) {
+        for pc in &self.pending_calls {
+            pc.handle.abort();
+        }
+    }
+    
+    pub fn fans_out(&self, argument: &ConformValue) -> bool {
+        self.options.fan_out_limit.is_some_and(|limit| {
+            !argument.is_certain() && argument.possibilities.len() <= limit && argument.failure().is_none()
+        })
+    }
+    
+    pub fn is_speculative(&self, call_id: &str) -> bool {
+        self.speculative_calls.values().any(|call| call.id == call_id)
+    }
+    
+    pub fn claim_speculation(&mut self, key: &str, assignment_var: &str) {
+        let Some(call) = self.speculative_calls.remove(key) else {
+            return;
+        };
+        self.speculation.used += 1;
+        if let Some(pc) = self.pending_calls.iter_mut().find(|pc| pc.id == call.id) {
+            pc.assignment_var = assignment_var.to_string();
+        }
+    }
+    
+    pub fn settle_speculation(&mut self, if_var: &str, then_taken: bool, else_taken: bool) {
+        let losing: Vec<String> = self.speculative_calls.iter()
+            .filter(|(_, call)| call.if_var == if_var)
+            .filter(|(_, call)| !(call.then_branch && then_taken || call.else_branch && else_taken))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in losing {
+            self.discard_speculation(&key);
+        }
+    }
+    
+    pub fn discard_speculation(&mut self, key: &str) {
+        let Some(call) = self.speculative_calls.remove(key) else {
+            return;
+        };
+        self.speculation.wasted += 1;
+        match self.pending_calls.iter().position(|pc| pc.id == call.id) {
+            Some(index) => {
+                let pc = self.pending_calls.remove(index);
+                pc.handle.abort();
+                self.inflight_calls.remove(key);
+                self.log(&format!("Cancelled speculative {} call {}", pc.function, pc.id));
+            }
+            None => {
+                self.completed_calls.remove(key);
+                self.log(&format!("Discarded result of speculative call {}", call.id));
+            }
+        }
+    }
+    
+    pub fn discard_all_speculation(&mut self) {
+        let keys: Vec<String> = self.speculative_calls.keys().cloned().collect();
+        for key in keys {
+            self.discard_speculation(&key);
         }
     }
     
@@ -35,7 +267,198 @@ impl ExecutionState {
         self.scope.get(name)
     }
     
+    pub fn log(&self, message: &str) {
+        if !self.options.quiet {
+            println!("{}", message);
+        }
+    }
+    
+    pub fn report(&self) -> ExecutionReport {
+        ExecutionReport {
+            status: self.status,
+            return_var: self.program.return_var.clone(),
+            return_value: self.lookup_var(&self.program.return_var).cloned(),
+            scope: self.scope.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            failures: self.failures.clone(),
+            rejected: self.rejected.clone(),
+            speculation: self.speculation,
+        }
+    }
+    
     pub fn set_var(&mut self, name: String, value: ConformValue) {
+        if let Some(log) = &mut self.binding_log {
+            log.push(name.clone());
+        }
         self.scope.insert(name, value);
     }
+    
+    pub fn bind_inputs(&mut self, mut inputs: HashMap<String, Value>) -> Result<()> {
+        let mut bound = Vec::new();
+        for input in &self.program.inputs {
+            let value = inputs.remove(&input.name).ok_or_else(|| {
+                QuasarError::InputError(format!("Missing input {}: {}", input.name, input.value_type))
+            })?;
+            if !input.value_type.accepts(&value) {
+                return Err(QuasarError::TypeError(format!(
+                    "Input {} expects {}, got {}",
+                    input.name, input.value_type, ValueType::of(&value)
+                )));
+            }
+            bound.push((input.name.clone(), value));
+        }
+        
+        if let Some(name) = inputs.keys().min() {
+            return Err(QuasarError::InputError(format!("Unknown input {}", name)));
+        }
+        for (name, value) in bound {
+            self.set_var(name, ConformValue::certain(value));
+        }
+        Ok(())
+    }
+    
+    pub fn begin_reduction(&mut self) {
+        if self.options.trace.is_some() {
+            self.binding_log = Some(Vec::new());
+        }
+    }
+    
+    pub fn record_reduction(&mut self, rule: Rule, before: &Statement, after: &[Statement]) -> Result<()> {
+        let names = self.binding_log.take().unwrap_or_default();
+        let Some(trace) = &self.options.trace else {
+            return Ok(());
+        };
+        
+        let scope_delta: BTreeMap<_, _> = names.into_iter()
+            .filter_map(|name| self.scope.get(&name).map(|value| (name, value.clone())))
+            .collect();
+        trace.record(rule, before.clone(), after.to_vec(), scope_delta)
+    }
+}
+
+impl Drop for ExecutionState {
+    fn drop(&mut self) {
+        self.abort_pending_calls();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::PrimitiveValue;
+    use crate::interpreter::execute_with_inputs;
+    use crate::runtime::testing::{registry, TestFunction};
+    use crate::syntax::parse_program;
+
+    async fn execute(source: &str, function: &TestFunction, failure_mode: FailureMode) -> Result<ExecutionState> {
+        let options = ExecutionOptions::new(false).with_quiet(true).with_failure_mode(failure_mode);
+        execute_with_inputs(parse_program(source).unwrap(), Arc::new(registry(&[function])), options, HashMap::new()).await
+    }
+
+    fn boolean(value: bool) -> Value {
+        Value::Primitive(PrimitiveValue::Boolean(value))
+    }
+
+    #[tokio::test]
+    async fn abort_stops_on_failure() {
+        let broken = TestFunction::failing("broken", ValueType::String);
+        let result = execute("a = \"q\"; x = broken(a); return x", &broken, FailureMode::Abort).await;
+        
+        assert!(matches!(result, Err(QuasarError::ExternalFunctionError(_))));
+    }
+
+    #[tokio::test]
+    async fn bind_error_continues_and_records_failure() {
+        let broken = TestFunction::failing("broken", ValueType::String);
+        let state = execute("a = \"q\"; x = broken(a); y = a; return y", &broken, FailureMode::BindError).await.unwrap();
+        
+        assert_eq!(state.status, ExecutionStatus::Completed);
+        assert!(state.lookup_var("x").unwrap().failure().is_some());
+        assert_eq!(state.failures.len(), 1);
+        assert_eq!(state.failures[0].assignment_var, "x");
+    }
+
+    #[tokio::test]
+    async fn bind_unknown_binds_every_possible_value() {
+        let broken = TestFunction::failing("broken", ValueType::Tuple(vec![ValueType::Boolean, ValueType::Null]));
+        let state = execute("a = \"q\"; x = broken(a); return x", &broken, FailureMode::BindUnknown).await.unwrap();
+        
+        let null = Value::Primitive(PrimitiveValue::Null);
+        let expected = ConformValue::uncertain([
+            Value::Tuple(vec![boolean(true), null.clone()]),
+            Value::Tuple(vec![boolean(false), null]),
+        ]);
+        assert_eq!(state.lookup_var("x"), Some(&expected));
+        assert_eq!(state.failures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn bind_unknown_rejects_unsupported_return_types() {
+        let broken = TestFunction::failing("broken", ValueType::String);
+        let result = execute("a = \"q\"; x = broken(a); return x", &broken, FailureMode::BindUnknown).await;
+        
+        assert!(matches!(result, Err(QuasarError::InvalidOperation(_))));
+        assert_eq!(broken.calls(), 0);
+    }
+    
+    const INPUTS: &str = "input name: String; input ids: List<Integer>; pair = (name, ids); return pair";
+
+    fn string(value: &str) -> Value {
+        Value::Primitive(PrimitiveValue::String(value.to_string()))
+    }
+
+    fn integers(values: &[i64]) -> Value {
+        Value::List(values.iter().map(|i| Value::Primitive(PrimitiveValue::Integer(*i))).collect())
+    }
+
+    #[test]
+    fn bind_inputs_binds_declared_inputs() {
+        let mut state = ExecutionState::new(parse_program(INPUTS).unwrap());
+        let inputs = HashMap::from([("name".to_string(), string("a")), ("ids".to_string(), integers(&[1, 2]))]);
+        state.bind_inputs(inputs).unwrap();
+        
+        assert_eq!(state.lookup_var("name"), Some(&ConformValue::certain(string("a"))));
+        assert_eq!(state.lookup_var("ids"), Some(&ConformValue::certain(integers(&[1, 2]))));
+    }
+
+    #[test]
+    fn bind_inputs_rejects_missing_mistyped_and_unknown_inputs() {
+        let mut state = ExecutionState::new(parse_program(INPUTS).unwrap());
+        let missing = HashMap::from([("name".to_string(), string("a"))]);
+        assert!(matches!(state.bind_inputs(missing), Err(QuasarError::InputError(message)) if message.contains("ids")));
+        
+        let mistyped = HashMap::from([("name".to_string(), string("a")), ("ids".to_string(), string("1"))]);
+        assert!(matches!(state.bind_inputs(mistyped), Err(QuasarError::TypeError(_))));
+        
+        let unknown = HashMap::from([
+            ("name".to_string(), string("a")),
+            ("ids".to_string(), integers(&[])),
+            ("other".to_string(), string("b")),
+        ]);
+        assert!(matches!(state.bind_inputs(unknown), Err(QuasarError::InputError(message)) if message.contains("other")));
+        assert!(state.lookup_var("name").is_none());
+    }
+
+    #[test]
+    fn inputs_from_json_converts_by_declared_type() {
+        let program = parse_program("input ratio: Float; input pair: (String, Integer); input rest; return ratio").unwrap();
+        let json = serde_json::json!({ "ratio": 1, "pair": ["a", 2], "rest": [true, null] });
+        let inputs = inputs_from_json(&program, json.as_object().unwrap()).unwrap();
+        
+        assert_eq!(inputs["ratio"], Value::Primitive(PrimitiveValue::Float(1.0.into())));
+        assert_eq!(inputs["pair"], Value::Tuple(vec![string("a"), Value::Primitive(PrimitiveValue::Integer(2))]));
+        assert_eq!(inputs["rest"], Value::List(vec![boolean(true), Value::Primitive(PrimitiveValue::Null)]));
+        
+        let json = serde_json::json!({ "pair": ["a", "b"] });
+        assert!(matches!(inputs_from_json(&program, json.as_object().unwrap()), Err(QuasarError::TypeError(_))));
+    }
+
+    #[tokio::test]
+    async fn execution_reads_bound_inputs() {
+        let options = ExecutionOptions::new(false).with_quiet(true);
+        let inputs = HashMap::from([("name".to_string(), string("a")), ("ids".to_string(), integers(&[3]))]);
+        let state = execute_with_inputs(parse_program(INPUTS).unwrap(), Arc::new(registry(&[])), options, inputs).await.unwrap();
+        
+        let expected = Value::Tuple(vec![string("a"), integers(&[3])]);
+        assert_eq!(state.lookup_var("pair"), Some(&ConformValue::certain(expected)));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::time::sleep;
+use crate::ast::{ConformValue, Value, ValueType};
+use super::error::{QuasarError, Result};
+use super::external::ExternalFunction;
+use super::registry::FunctionRegistry;
+use super::signature::{Effects, FunctionSignature};
+
+#[derive(Clone)]
+enum Outcome {
+    Echo,
+    Constant(Value),
+    Fail,
+}
+
+#[derive(Clone)]
+pub struct TestFunction {
+    signature: FunctionSignature,
+    outcome: Outcome,
+    delay: Duration,
+    calls: Arc<AtomicUsize>,
+}
+
+impl TestFunction {
+    fn new(name: &str, return_type: ValueType, outcome: Outcome) -> Self {
+        TestFunction {
+            signature: FunctionSignature::new(name, ValueType::Any, return_type)
+                .with_effects(Effects { pure: true, idempotent: true }),
+            outcome,
+            delay: Duration::ZERO,
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn echo(name: &str) -> Self {
+        TestFunction::new(name, ValueType::Any, Outcome::Echo)
+    }
+
+    pub fn constant(name: &str, value: Value) -> Self {
+        let return_type = ValueType::of(&value);
+        TestFunction::new(name, return_type, Outcome::Constant(value))
+    }
+
+    pub fn failing(name: &str, return_type: ValueType) -> Self {
+        TestFunction::new(name, return_type, Outcome::Fail)
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ExternalFunction for TestFunction {
+    fn signature(&self) -> FunctionSignature {
+        self.signature.clone()
+    }
+
+    async fn call(&self, args: &ConformValue) -> Result<ConformValue> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        sleep(self.delay).await;
+        match &self.outcome {
+            Outcome::Echo => Ok(args.clone()),
+            Outcome::Constant(value) => Ok(ConformValue::certain(value.clone())),
+            Outcome::Fail => Err(QuasarError::ExternalFunctionError(format!("{} failed", self.signature.name))),
+        }
+    }
+}
+
+pub fn registry(functions: &[&TestFunction]) -> FunctionRegistry {
+    let mut registry = FunctionRegistry::new();
+    for function in functions {
+        registry.register((*function).clone());
+    }
+    registry
+}
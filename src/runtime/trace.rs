@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::ast::{ConformValue, Statement};
+use super::error::{QuasarError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    Alias,
+    Proj,
+    IfT,
+    IfF,
+    IfTf,
+    Fold,
+    Try,
+    Catch,
+    Err,
+    Prim,
+    Tuple,
+    Join,
+    Ext,
+    Disp,
+    Fan,
+    Spec,
+}
+
+impl Rule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::Alias => "alias",
+            Rule::Proj => "proj",
+            Rule::IfT => "if-t",
+            Rule::IfF => "if-f",
+            Rule::IfTf => "if-tf",
+            Rule::Fold => "fold",
+            Rule::Try => "try",
+            Rule::Catch => "catch",
+            Rule::Err => "err",
+            Rule::Prim => "prim",
+            Rule::Tuple => "tuple",
+            Rule::Join => "join",
+            Rule::Ext => "ext",
+            Rule::Disp => "disp",
+            Rule::Fan => "fan",
+            Rule::Spec => "spec",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub sequence: usize,
+    pub rule: Rule,
+    pub before: Statement,
+    pub after: Vec<Statement>,
+    pub scope_delta: BTreeMap<String, ConformValue>,
+}
+
+#[derive(Debug, Default)]
+pub struct Trace {
+    entries: Mutex<Vec<TraceEntry>>,
+    file: Option<Mutex<File>>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Trace::default()
+    }
+
+    pub fn to_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|e| {
+            QuasarError::RuntimeError(format!("Failed to create trace {}: {}", path.display(), e))
+        })?;
+        Ok(Trace {
+            entries: Mutex::new(Vec::new()),
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    pub fn record(
+        &self,
+        rule: Rule,
+        before: Statement,
+        after: Vec<Statement>,
+        scope_delta: BTreeMap<String, ConformValue>,
+    ) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = TraceEntry {
+            sequence: entries.len(),
+            rule,
+            before,
+            after,
+            scope_delta,
+        };
+        
+        if let Some(file) = &self.file {
+            let line = serde_json::to_string(&entry).map_err(|e| {
+                QuasarError::RuntimeError(format!("Failed to serialize trace entry: {}", e))
+            })?;
+            let mut file = file.lock().unwrap();
+            writeln!(file, "{}", line).map_err(|e| {
+                QuasarError::RuntimeError(format!("Failed to write trace entry: {}", e))
+            })?;
+        }
+        
+        entries.push(entry);
+        Ok(())
+    }
+
+    pub fn entries(&self) -> Vec<TraceEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    pub fn to_json_lines(&self) -> Result<String> {
+        let mut lines = String::new();
+        for entry in self.entries.lock().unwrap().iter() {
+            let line = serde_json::to_string(entry).map_err(|e| {
+                QuasarError::RuntimeError(format!("Failed to serialize trace entry: {}", e))
+            })?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        Ok(lines)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::runtime::{QuasarError, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Identifier(String),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Equals,
+    Arrow,
+    Semicolon,
+    Colon,
+    Comma,
+    LessThan,
+    GreaterThan,
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub line: usize,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>> {
+    tokenize_with_comments(source).map(|(tokens, _)| tokens)
+}
+
+pub fn tokenize_with_comments(source: &str) -> Result<(Vec<Token>, Vec<Comment>)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut comments = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+    let mut column = 1;
+    
+    while pos < chars.len() {
+        let c = chars[pos];
+        let (start_line, start_column) = (line, column);
+        
+        if c == '\n' {
+            pos += 1;
+            line += 1;
+            column = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            pos += 1;
+            column += 1;
+            continue;
+        }
+        if c == '#' || (c == '/' && chars.get(pos + 1) == Some(&'/')) {
+            let start = pos;
+            while pos < chars.len() && chars[pos] != '\n' {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            comments.push(Comment { text: text.trim_end().to_string(), line });
+            continue;
+        }
+        
+        let start = pos;
+        let kind = if c.is_alphabetic() || c == '_' || c == '?' {
+            pos += 1;
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            TokenKind::Identifier(chars[start..pos].iter().collect())
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(pos + 1).is_some_and(|d| d.is_ascii_digit())) {
+            pos += 1;
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            let is_float = chars.get(pos) == Some(&'.') && chars.get(pos + 1).is_some_and(|d| d.is_ascii_digit());
+            if is_float {
+                pos += 1;
+                while pos < chars.len() && chars[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+            }
+            let text: String = chars[start..pos].iter().collect();
+            let parsed = if is_float {
+                text.parse().ok().map(TokenKind::Float)
+            } else {
+                text.parse().ok().map(TokenKind::Integer)
+            };
+            parsed.ok_or_else(|| QuasarError::ParseError {
+                line: start_line,
+                column: start_column,
+                message: format!("Invalid number literal: {}", text),
+            })?
+        } else if c == '"' {
+            pos += 1;
+            let mut text = String::new();
+            loop {
+                let Some(&next) = chars.get(pos) else {
+                    return Err(QuasarError::ParseError {
+                        line: start_line,
+                        column: start_column,
+                        message: "Unterminated string literal".to_string(),
+                    });
+                };
+                pos += 1;
+                match next {
+                    '"' => break,
+                    '\\' => {
+                        let escaped = chars.get(pos).copied();
+                        pos += 1;
+                        text.push(match escaped {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('r') => '\r',
+                            Some('"') => '"',
+                            Some('\\') => '\\',
+                            other => {
+                                return Err(QuasarError::ParseError {
+                                    line: start_line,
+                                    column: start_column,
+                                    message: format!("Invalid escape sequence: \\{}", other.unwrap_or(' ')),
+                                });
+                            }
+                        });
+                    }
+                    '\n' => {
+                        line += 1;
+                        text.push(next);
+                    }
+                    _ => text.push(next),
+                }
+            }
+            TokenKind::String(text)
+        } else {
+            pos += 1;
+            match c {
+                '=' if chars.get(pos) == Some(&'>') => {
+                    pos += 1;
+                    TokenKind::Arrow
+                }
+                '=' => TokenKind::Equals,
+                ';' => TokenKind::Semicolon,
+                ':' => TokenKind::Colon,
+                '<' => TokenKind::LessThan,
+                '>' => TokenKind::GreaterThan,
+                ',' => TokenKind::Comma,
+                '(' => TokenKind::LeftParen,
+                ')' => TokenKind::RightParen,
+                '{' => TokenKind::LeftBrace,
+                '}' => TokenKind::RightBrace,
+                '[' => TokenKind::LeftBracket,
+                ']' => TokenKind::RightBracket,
+                _ => {
+                    return Err(QuasarError::ParseError {
+                        line: start_line,
+                        column: start_column,
+                        message: format!("Unexpected character: {}", c),
+                    });
+                }
+            }
+        };
+        
+        let consumed = &chars[start..pos];
+        let trailing = consumed.iter().rev().take_while(|&&ch| ch != '\n').count();
+        column = if consumed.contains(&'\n') { trailing + 1 } else { column + trailing };
+        tokens.push(Token { kind, line: start_line, column: start_column });
+    }
+    
+    tokens.push(Token { kind: TokenKind::Eof, line, column });
+    Ok((tokens, comments))
+}
@@ -0,0 +1,7 @@
+pub mod lexer;
+pub mod parser;
+pub mod printer;
+
+pub use lexer::*;
+pub use parser::*;
+pub use printer::*;
\ No newline at end of file
@@ -0,0 +1,484 @@
+use crate::ast::*;
+use crate::runtime::{QuasarError, Result};
+use super::lexer::{tokenize_with_comments, Comment, Token, TokenKind};
+
+const KEYWORDS: &[&str] = &["input", "return", "proj", "fold", "if", "else", "join", "try", "true", "false", "null"];
+const MAX_NESTING: usize = 64;
+
+pub fn parse_program(source: &str) -> Result<Program> {
+    parse_program_with_symbols(source).map(|(program, _)| program)
+}
+
+pub fn parse_program_with_symbols(source: &str) -> Result<(Program, Vec<Symbol>)> {
+    let mut parser = Parser::new(source)?;
+    let inputs = parser.inputs()?;
+    let program = parser.program()?;
+    parser.expect(TokenKind::Eof)?;
+    Ok((Program { inputs, ..program }, parser.symbols))
+}
+
+pub fn parse_program_with_trivia(source: &str) -> Result<(Program, Trivia)> {
+    let mut parser = Parser::new(source)?;
+    let inputs = parser.inputs()?;
+    let program = parser.program()?;
+    parser.expect(TokenKind::Eof)?;
+    let trivia = Trivia {
+        comments: parser.comments,
+        lines: parser.lines,
+        trailing: parser.trailing,
+    };
+    Ok((Program { inputs, ..program }, trivia))
+}
+
+pub fn parse_statements(source: &str) -> Result<(Vec<Statement>, Option<String>)> {
+    let mut parser = Parser::new(source)?;
+    let mut statements = Vec::new();
+    
+    while !parser.at(&TokenKind::Eof) && !parser.at_keyword("return") {
+        statements.push(parser.statement()?);
+    }
+    
+    let return_var = if parser.eat_keyword("return") {
+        let var = parser.symbol(SymbolKind::Reference)?;
+        parser.eat(&TokenKind::Semicolon);
+        Some(var)
+    } else {
+        None
+    };
+    parser.expect(TokenKind::Eof)?;
+    
+    Ok((statements, return_var))
+}
+
+pub fn parse_value(source: &str) -> Result<Value> {
+    let mut parser = Parser::new(source)?;
+    let value = parser.literal()?;
+    parser.expect(TokenKind::Eof)?;
+    Ok(value)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Input,
+    Definition,
+    Parameter,
+    Reference,
+    Function,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trivia {
+    pub comments: Vec<Comment>,
+    pub lines: Vec<usize>,
+    pub trailing: Vec<Option<usize>>,
+}
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    symbols: Vec<Symbol>,
+    comments: Vec<Comment>,
+    lines: Vec<usize>,
+    trailing: Vec<Option<usize>>,
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new(source: &str) -> Result<Self> {
+        let (tokens, comments) = tokenize_with_comments(source)?;
+        Ok(Parser {
+            tokens,
+            pos: 0,
+            symbols: Vec::new(),
+            comments,
+            lines: Vec::new(),
+            trailing: Vec::new(),
+            depth: 0,
+        })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn at(&self, kind: &TokenKind) -> bool {
+        &self.peek().kind == kind
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Identifier(name) if name == keyword)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.at(kind) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.at_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> QuasarError {
+        let token = self.peek();
+        QuasarError::ParseError {
+            line: token.line,
+            column: token.column,
+            message: message.into(),
+        }
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_NESTING {
+            return Err(self.error(format!("Nesting deeper than {} levels", MAX_NESTING)));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<()> {
+        if self.eat(&kind) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected {}, found {}", describe(&kind), describe(&self.peek().kind))))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected '{}', found {}", keyword, describe(&self.peek().kind))))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        match &self.peek().kind {
+            TokenKind::Identifier(name) if !KEYWORDS.contains(&name.as_str()) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            other => Err(self.error(format!("Expected variable name, found {}", describe(other)))),
+        }
+    }
+
+    fn peek_next(&self) -> &TokenKind {
+        &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)].kind
+    }
+
+    fn symbol(&mut self, kind: SymbolKind) -> Result<String> {
+        let (line, column) = (self.peek().line, self.peek().column);
+        let name = self.identifier()?;
+        self.symbols.push(Symbol { name: name.clone(), kind, line, column });
+        Ok(name)
+    }
+
+    fn start_line(&mut self) -> usize {
+        self.lines.push(self.peek().line);
+        self.trailing.push(None);
+        self.lines.len() - 1
+    }
+
+    fn end_line(&mut self, start: usize) {
+        let line = self.tokens[self.pos - 1].line;
+        if self.at(&TokenKind::Eof) || self.peek().line > line {
+            self.trailing[start] = self.comments.iter().position(|comment| comment.line == line);
+        }
+    }
+
+    fn identifier_list(&mut self, close: TokenKind) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        if self.eat(&close) {
+            return Ok(names);
+        }
+        loop {
+            names.push(self.symbol(SymbolKind::Reference)?);
+            if self.eat(&close) {
+                return Ok(names);
+            }
+            self.expect(TokenKind::Comma)?;
+        }
+    }
+
+    pub fn program(&mut self) -> Result<Program> {
+        let mut statements = Vec::new();
+        while !self.at_keyword("return") {
+            if self.at(&TokenKind::Eof) || self.at(&TokenKind::RightBrace) {
+                return Err(self.error("Expected 'return' at the end of the program"));
+            }
+            statements.push(self.statement()?);
+        }
+        let start = self.start_line();
+        self.expect_keyword("return")?;
+        let return_var = self.symbol(SymbolKind::Reference)?;
+        self.eat(&TokenKind::Semicolon);
+        self.end_line(start);
+        
+        Ok(Program { inputs: Vec::new(), statements, return_var })
+    }
+
+    pub fn inputs(&mut self) -> Result<Vec<Input>> {
+        let mut inputs = Vec::new();
+        while self.at_keyword("input") {
+            let start = self.start_line();
+            self.advance();
+            let name = self.symbol(SymbolKind::Input)?;
+            let value_type = if self.eat(&TokenKind::Colon) {
+                self.value_type()?
+            } else {
+                ValueType::Any
+            };
+            self.expect(TokenKind::Semicolon)?;
+            self.end_line(start);
+            inputs.push(Input { name, value_type });
+        }
+        Ok(inputs)
+    }
+
+    fn value_type(&mut self) -> Result<ValueType> {
+        if self.eat(&TokenKind::LeftParen) {
+            let mut elements = Vec::new();
+            if self.eat(&TokenKind::RightParen) {
+                return Ok(ValueType::Tuple(elements));
+            }
+            loop {
+                elements.push(self.nested(Self::value_type)?);
+                if self.eat(&TokenKind::RightParen) {
+                    return Ok(ValueType::Tuple(elements));
+                }
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+        
+        let TokenKind::Identifier(name) = self.peek().kind.clone() else {
+            return Err(self.error(format!("Expected a type, found {}", describe(&self.peek().kind))));
+        };
+        let value_type = match name.as_str() {
+            "Any" => ValueType::Any,
+            "Boolean" => ValueType::Boolean,
+            "Integer" => ValueType::Integer,
+            "Float" => ValueType::Float,
+            "String" => ValueType::String,
+            "Null" => ValueType::Null,
+            "Error" => ValueType::Error,
+            "List" => {
+                self.advance();
+                self.expect(TokenKind::LessThan)?;
+                let element = self.nested(Self::value_type)?;
+                self.expect(TokenKind::GreaterThan)?;
+                return Ok(ValueType::List(Box::new(element)));
+            }
+            _ => return Err(self.error(format!("Unknown type {}", name))),
+        };
+        self.advance();
+        Ok(value_type)
+    }
+
+    pub fn statement(&mut self) -> Result<Statement> {
+        let start = self.start_line();
+        let variable = self.symbol(SymbolKind::Definition)?;
+        self.expect(TokenKind::Equals)?;
+        let expression = self.expression()?;
+        self.expect(TokenKind::Semicolon)?;
+        self.end_line(start);
+        
+        Ok(Statement { variable, expression })
+    }
+
+    fn expression(&mut self) -> Result<Expression> {
+        let kind = self.peek().kind.clone();
+        match kind {
+            TokenKind::Identifier(name) => match name.as_str() {
+                "true" | "false" | "null" => Ok(Expression::Primitive(self.literal()?)),
+                "proj" => {
+                    self.advance();
+                    let TokenKind::Integer(index @ 0..) = self.peek().kind else {
+                        return Err(self.error("Expected a non-negative tuple index after 'proj'"));
+                    };
+                    self.advance();
+                    let index = index as usize;
+                    let variable = self.symbol(SymbolKind::Reference)?;
+                    Ok(Expression::Projection { index, variable })
+                }
+                "fold" => {
+                    self.advance();
+                    let list = self.symbol(SymbolKind::Reference)?;
+                    let initial = self.symbol(SymbolKind::Reference)?;
+                    let block = self.block(None)?;
+                    Ok(Expression::Fold { list, initial, block })
+                }
+                "if" => {
+                    self.advance();
+                    let condition = self.symbol(SymbolKind::Reference)?;
+                    let then_block = self.block(Some("_"))?;
+                    let else_block = if self.eat_keyword("else") {
+                        Some(self.block(Some("_"))?)
+                    } else {
+                        None
+                    };
+                    Ok(Expression::If { condition, then_block, else_block })
+                }
+                "join" => {
+                    self.advance();
+                    self.expect(TokenKind::LeftBrace)?;
+                    Ok(Expression::Join(self.identifier_list(TokenKind::RightBrace)?))
+                }
+                "try" => {
+                    self.advance();
+                    let body = self.block(Some("_"))?;
+                    self.expect_keyword("else")?;
+                    let handler = self.block(None)?;
+                    Ok(Expression::Try { body, handler })
+                }
+                _ => {
+                    let kind = if *self.peek_next() == TokenKind::LeftParen {
+                        SymbolKind::Function
+                    } else {
+                        SymbolKind::Reference
+                    };
+                    let name = self.symbol(kind)?;
+                    if self.eat(&TokenKind::LeftParen) {
+                        let argument = self.symbol(SymbolKind::Reference)?;
+                        self.expect(TokenKind::RightParen)?;
+                        Ok(Expression::ExternalCall { function: name, argument })
+                    } else {
+                        Ok(Expression::Variable(name))
+                    }
+                }
+            },
+            TokenKind::LeftParen => {
+                self.advance();
+                Ok(Expression::Tuple(self.identifier_list(TokenKind::RightParen)?))
+            }
+            _ => Ok(Expression::Primitive(self.literal()?)),
+        }
+    }
+
+    fn block(&mut self, parameter: Option<&str>) -> Result<Block> {
+        self.expect(TokenKind::LeftBrace)?;
+        let parameter = match parameter {
+            Some(parameter) => parameter.to_string(),
+            None => {
+                let parameter = self.symbol(SymbolKind::Parameter)?;
+                self.expect(TokenKind::Arrow)?;
+                parameter
+            }
+        };
+        let program = self.nested(Self::program)?;
+        self.expect(TokenKind::RightBrace)?;
+        
+        Ok(Block {
+            parameter,
+            body: program.statements,
+            return_var: program.return_var,
+        })
+    }
+
+    fn literal(&mut self) -> Result<Value> {
+        let primitive = match self.peek().kind.clone() {
+            TokenKind::Identifier(name) if name == "true" => PrimitiveValue::Boolean(true),
+            TokenKind::Identifier(name) if name == "false" => PrimitiveValue::Boolean(false),
+            TokenKind::Identifier(name) if name == "null" => PrimitiveValue::Null,
+            TokenKind::Integer(i) => PrimitiveValue::Integer(i),
+            TokenKind::Float(f) => PrimitiveValue::Float(ordered_float::OrderedFloat(f)),
+            TokenKind::String(s) => PrimitiveValue::String(s),
+            TokenKind::LeftBracket => {
+                self.advance();
+                let mut items = Vec::new();
+                if self.eat(&TokenKind::RightBracket) {
+                    return Ok(Value::List(items));
+                }
+                loop {
+                    items.push(self.nested(Self::literal)?);
+                    if self.eat(&TokenKind::RightBracket) {
+                        return Ok(Value::List(items));
+                    }
+                    self.expect(TokenKind::Comma)?;
+                }
+            }
+            other => return Err(self.error(format!("Expected an expression, found {}", describe(&other)))),
+        };
+        self.advance();
+        Ok(Value::Primitive(primitive))
+    }
+}
+
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Identifier(name) => format!("'{}'", name),
+        TokenKind::Integer(i) => format!("'{}'", i),
+        TokenKind::Float(f) => format!("'{:?}'", f),
+        TokenKind::String(s) => format!("{:?}", s),
+        TokenKind::Equals => "'='".to_string(),
+        TokenKind::Arrow => "'=>'".to_string(),
+        TokenKind::Semicolon => "';'".to_string(),
+        TokenKind::Colon => "':'".to_string(),
+        TokenKind::LessThan => "'<'".to_string(),
+        TokenKind::GreaterThan => "'>'".to_string(),
+        TokenKind::Comma => "','".to_string(),
+        TokenKind::LeftParen => "'('".to_string(),
+        TokenKind::RightParen => "')'".to_string(),
+        TokenKind::LeftBrace => "'{'".to_string(),
+        TokenKind::RightBrace => "'}'".to_string(),
+        TokenKind::LeftBracket => "'['".to_string(),
+        TokenKind::RightBracket => "']'".to_string(),
+        TokenKind::Eof => "end of input".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nesting_error(result: Result<impl std::fmt::Debug>) -> bool {
+        matches!(result, Err(QuasarError::ParseError { message, .. }) if message.starts_with("Nesting deeper"))
+    }
+
+    #[test]
+    fn deep_lists_are_rejected() {
+        assert!(nesting_error(parse_value(&"[".repeat(200_000))));
+        assert!(nesting_error(parse_program(&format!("x = {}; return x", "[".repeat(200_000)))));
+        assert!(parse_value(&format!("{}1{}", "[".repeat(MAX_NESTING), "]".repeat(MAX_NESTING))).is_ok());
+    }
+
+    #[test]
+    fn deep_blocks_are_rejected() {
+        assert!(nesting_error(parse_program(&"x = if c { ".repeat(100_000))));
+        assert!(nesting_error(parse_program(&"x = fold l i { a => ".repeat(100_000))));
+        let nested = format!("{}return c }}{}; return x", "x = if c { ".repeat(MAX_NESTING), "; return x }".repeat(MAX_NESTING - 1));
+        assert!(parse_program(&nested).is_ok());
+    }
+
+    #[test]
+    fn deep_types_are_rejected() {
+        assert!(nesting_error(parse_program(&format!("input x: {}", "List<".repeat(200_000)))));
+        assert!(nesting_error(parse_program(&format!("input x: {}", "(".repeat(200_000)))));
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use crate::ast::*;
+use super::lexer::Comment;
+use super::parser::Trivia;
+
+const INDENT: &str = "    ";
+
+#[derive(Default)]
+struct Comments {
+    comments: VecDeque<(usize, Comment)>,
+    lines: VecDeque<(usize, Option<usize>)>,
+}
+
+impl Comments {
+    fn new(trivia: &Trivia) -> Self {
+        Comments {
+            comments: trivia.comments.iter().cloned().enumerate().collect(),
+            lines: trivia.lines.iter().copied().zip(trivia.trailing.iter().copied()).collect(),
+        }
+    }
+
+    fn leading(&mut self, out: &mut String, depth: usize) -> Option<usize> {
+        let (line, trailing) = self.lines.pop_front()?;
+        while let Some((_, comment)) = self.comments.pop_front_if(|(_, comment)| comment.line < line) {
+            out.push_str(&INDENT.repeat(depth));
+            out.push_str(&comment.text);
+            out.push('\n');
+        }
+        trailing
+    }
+
+    fn trailing(&mut self, out: &mut String, trailing: Option<usize>) {
+        let position = trailing.and_then(|index| self.comments.iter().position(|(i, _)| *i == index));
+        if let Some((_, comment)) = position.and_then(|position| self.comments.remove(position)) {
+            out.push(' ');
+            out.push_str(&comment.text);
+        }
+    }
+
+    fn finish(&mut self, out: &mut String) {
+        for (_, comment) in self.comments.drain(..) {
+            out.push('\n');
+            out.push_str(&comment.text);
+        }
+    }
+}
+
+pub fn print_program(program: &Program) -> String {
+    print_program_with_trivia(program, &Trivia::default())
+}
+
+pub fn print_program_with_trivia(program: &Program, trivia: &Trivia) -> String {
+    let mut comments = Comments::new(trivia);
+    let mut out = String::new();
+    for input in &program.inputs {
+        let trailing = comments.leading(&mut out, 0);
+        out.push_str(&format!("input {}: {};", input.name, input.value_type));
+        comments.trailing(&mut out, trailing);
+        out.push('\n');
+    }
+    write_body(&mut out, &program.statements, &program.return_var, 0, &mut comments);
+    comments.finish(&mut out);
+    out
+}
+
+pub fn print_statement(statement: &Statement) -> String {
+    let mut out = String::new();
+    write_statement(&mut out, statement, 0, &mut Comments::default());
+    out
+}
+
+pub fn print_expression(expression: &Expression) -> String {
+    let mut out = String::new();
+    write_expression(&mut out, expression, 0, &mut Comments::default());
+    out
+}
+
+pub fn print_value(value: &Value) -> String {
+    match value {
+        Value::Primitive(PrimitiveValue::Boolean(b)) => b.to_string(),
+        Value::Primitive(PrimitiveValue::Integer(i)) => i.to_string(),
+        Value::Primitive(PrimitiveValue::Float(f)) => format!("{:?}", f.into_inner()),
+        Value::Primitive(PrimitiveValue::String(s)) => quote(s),
+        Value::Primitive(PrimitiveValue::Null) => "null".to_string(),
+        Value::List(items) => format!("[{}]", join(items.iter().map(print_value))),
+        Value::Tuple(items) => format!("({})", join(items.iter().map(print_value))),
+        Value::Error(message) => format!("error({})", quote(message)),
+        Value::Rejected(function) => format!("rejected({})", quote(function)),
+    }
+}
+
+pub fn print_conform_value(value: &ConformValue) -> String {
+    match value.as_certain() {
+        Some(value) => print_value(value),
+        None => format!("{{{}}}", value.possibilities.iter().map(print_value).collect::<Vec<_>>().join(" | ")),
+    }
+}
+
+fn write_body(out: &mut String, statements: &[Statement], return_var: &str, depth: usize, comments: &mut Comments) {
+    for statement in statements {
+        let trailing = comments.leading(out, depth);
+        write_statement(out, statement, depth, comments);
+        comments.trailing(out, trailing);
+        out.push('\n');
+    }
+    let trailing = comments.leading(out, depth);
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str("return ");
+    out.push_str(return_var);
+    comments.trailing(out, trailing);
+}
+
+fn write_statement(out: &mut String, statement: &Statement, depth: usize, comments: &mut Comments) {
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str(&statement.variable);
+    out.push_str(" = ");
+    write_expression(out, &statement.expression, depth, comments);
+    out.push(';');
+}
+
+fn write_block(out: &mut String, block: &Block, with_parameter: bool, depth: usize, comments: &mut Comments) {
+    out.push('{');
+    if with_parameter {
+        out.push_str(&block.parameter);
+        out.push_str(" =>");
+    }
+    out.push('\n');
+    write_body(out, &block.body, &block.return_var, depth + 1, comments);
+    out.push('\n');
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+}
+
+fn write_expression(out: &mut String, expression: &Expression, depth: usize, comments: &mut Comments) {
+    match expression {
+        Expression::Primitive(value) => out.push_str(&print_value(value)),
+        Expression::Variable(name) => out.push_str(name),
+        Expression::Tuple(vars) => out.push_str(&format!("({})", vars.join(", "))),
+        Expression::ExternalCall { function, argument } => {
+            out.push_str(&format!("{}({})", function, argument));
+        }
+        Expression::Projection { index, variable } => {
+            out.push_str(&format!("proj {} {}", index, variable));
+        }
+        Expression::Fold { list, initial, block } => {
+            out.push_str(&format!("fold {} {} ", list, initial));
+            write_block(out, block, true, depth, comments);
+        }
+        Expression::If { condition, then_block, else_block } => {
+            out.push_str(&format!("if {} ", condition));
+            write_block(out, then_block, false, depth, comments);
+            if let Some(else_block) = else_block {
+                out.push_str(" else ");
+                write_block(out, else_block, false, depth, comments);
+            }
+        }
+        Expression::Try { body, handler } => {
+            out.push_str("try ");
+            write_block(out, body, false, depth, comments);
+            out.push_str(" else ");
+            write_block(out, handler, true, depth, comments);
+        }
+        Expression::Catch { value, handler } => {
+            out.push_str(&format!("catch {} ", value));
+            write_block(out, handler, true, depth, comments);
+        }
+        Expression::PendingCall(id) => out.push_str(&format!("pending {}", id)),
+        Expression::AbstractPrimitive(value) => out.push_str(&print_conform_value(value)),
+        Expression::AbstractList(items) => {
+            let items = items.iter().map(|(value, certain)| {
+                if *certain {
+                    print_value(value)
+                } else {
+                    format!("{}?", print_value(value))
+                }
+            });
+            out.push_str(&format!("[{}]", join(items)));
+        }
+        Expression::Join(vars) => out.push_str(&format!("join {{{}}}", vars.join(", "))),
+    }
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::{parse_program, parse_program_with_trivia};
+
+    const PROGRAM: &str = r#"
+input name: String;
+input limit: Integer;
+flag = true;
+ratio = 1.5;
+nothing = null;
+items = [1, 2, 3];
+pair = (name, limit);
+first = proj 0 pair;
+found = find(first);
+total = fold items limit {p => acc = proj 0 p; return acc};
+chosen = if flag { a = first; return a } else { b = name; return b };
+either = join {first, name};
+safe = try { r = find(name); return r } else { e => return e };
+return safe
+"#;
+
+    fn round_trip(source: &str) {
+        let program = parse_program(source).unwrap();
+        let printed = print_program(&program);
+        assert_eq!(parse_program(&printed).unwrap(), program);
+        assert_eq!(print_program(&parse_program(&printed).unwrap()), printed);
+    }
+
+    #[test]
+    fn round_trips_every_construct() {
+        round_trip(PROGRAM);
+    }
+
+    #[test]
+    fn round_trips_sample() {
+        round_trip(include_str!("../../examples/sample.qsr"));
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let source = "\
+# leading
+x = 1; // trailing
+y = if x {
+    // nested
+    r = x;
+    return r
+} else {
+    return x
+};
+return y
+# final
+";
+        let (program, trivia) = parse_program_with_trivia(source).unwrap();
+        let printed = print_program_with_trivia(&program, &trivia);
+        for comment in ["# leading", "// trailing", "// nested", "# final"] {
+            assert!(printed.contains(comment), "{} missing from\n{}", comment, printed);
+        }
+        assert!(printed.contains("x = 1; // trailing"));
+        
+        let (reparsed, trivia) = parse_program_with_trivia(&printed).unwrap();
+        assert_eq!(reparsed, program);
+        assert_eq!(print_program_with_trivia(&reparsed, &trivia), printed);
+    }
+    
+    fn keeps_trailing_comment(source: &str, line: &str) {
+        let (program, trivia) = parse_program_with_trivia(source).unwrap();
+        let printed = print_program_with_trivia(&program, &trivia);
+        assert!(printed.lines().any(|printed| printed == line), "{:?} missing from\n{}", line, printed);
+        
+        let (reparsed, trivia) = parse_program_with_trivia(&printed).unwrap();
+        assert_eq!(reparsed, program);
+        assert_eq!(print_program_with_trivia(&reparsed, &trivia), printed);
+    }
+
+    #[test]
+    fn keeps_trailing_comment_after_block() {
+        keeps_trailing_comment("c = true; x = if c { a = c; return a }; // inline\nreturn x", "}; // inline");
+        keeps_trailing_comment(
+            "l = [1, 2];\ni = 0;\nr = fold l i { p =>\n    a = proj 0 p;\n    return a\n}; // after fold\nreturn r",
+            "}; // after fold",
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::io::{BufRead, Write};
+use serde_json::Value as Json;
+use tokio::sync::mpsc;
+
+pub fn read_message(reader: &mut impl BufRead) -> std::io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    
+    let length = length.ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "Missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Json) -> std::io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+pub fn spawn_stdin_reader() -> mpsc::UnboundedReceiver<Json> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        let mut reader = stdin.lock();
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}